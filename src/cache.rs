@@ -0,0 +1,196 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use crate::common::{ParamSpec, Params, Part};
+
+/// 64-bit FNV-1a. Not cryptographic, but stable across builds and platforms,
+/// unlike `DefaultHasher`, which matters for something that lives on disk.
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ *byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+/// Identifies the solver code by hashing the running executable, so any
+/// rebuild that changes the binary invalidates everything cached before it.
+pub(crate) fn build_id() -> io::Result<u64> {
+    Ok(fnv1a(&fs::read(std::env::current_exe()?)?))
+}
+
+pub(crate) struct Key {
+    day: usize,
+    part: Part,
+    input_hash: u64,
+    params_hash: u64,
+    build_id: u64,
+}
+
+impl Key {
+    /// Fails if a file named by one of the params can't be read, since
+    /// there's no telling then whether the answer is still right.
+    pub fn new(
+        day: usize,
+        part: Part,
+        input: &str,
+        params: &Params,
+        specs: &[ParamSpec],
+        build_id: u64,
+    ) -> io::Result<Self> {
+        // parameters change answers too, so they're part of the key
+        let mut key = params
+            .assignments()
+            .iter()
+            .map(|(k, v)| format!("{k}={v}\n"))
+            .collect::<String>();
+
+        // and so does whatever's in the files they point at
+        for spec in specs.iter().filter(|spec| spec.file) {
+            let path = params.get::<String>(spec).map_err(io::Error::other)?;
+            if path.is_empty() {
+                continue;
+            }
+            let contents = fs::read(&path)
+                .map_err(|e| io::Error::new(e.kind(), format!("can't read {path}: {e}")))?;
+            key.push_str(&format!(
+                "{} contents={:016x}\n",
+                spec.name,
+                fnv1a(&contents)
+            ));
+        }
+
+        Ok(Self {
+            day,
+            part,
+            input_hash: fnv1a(input.as_bytes()),
+            params_hash: fnv1a(key.as_bytes()),
+            build_id,
+        })
+    }
+
+    fn path(&self) -> PathBuf {
+        PathBuf::from(format!("{:016x}", self.build_id)).join(format!(
+            "{:02}-{}-{:016x}-{:016x}",
+            self.day, self.part, self.input_hash, self.params_hash
+        ))
+    }
+}
+
+pub(crate) struct Cache {
+    dir: PathBuf,
+}
+
+impl Cache {
+    /// The cache lives in `$XDG_CACHE_HOME/aoc2024`, falling back to
+    /// `~/.cache/aoc2024`. Returns `None` if neither is set.
+    pub fn open() -> Option<Self> {
+        let base = std::env::var_os("XDG_CACHE_HOME")
+            .map(PathBuf::from)
+            .or_else(|| std::env::var_os("HOME").map(|home| Path::new(&home).join(".cache")))?;
+        Some(Self::at(base.join("aoc2024")))
+    }
+
+    pub fn at(dir: PathBuf) -> Self {
+        Self { dir }
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    pub fn get(&self, key: &Key) -> io::Result<Option<String>> {
+        match fs::read_to_string(self.dir.join(key.path())) {
+            Ok(answer) => Ok(Some(answer)),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    pub fn put(&self, key: &Key, answer: &str) -> io::Result<()> {
+        let path = self.dir.join(key.path());
+        fs::create_dir_all(path.parent().unwrap())?;
+        // renaming is atomic, so another run never sees half an answer
+        let mut temp = path.clone().into_os_string();
+        temp.push(format!(".tmp-{}", std::process::id()));
+        fs::write(&temp, answer)?;
+        fs::rename(&temp, path)
+    }
+
+    pub fn clear(&self) -> io::Result<()> {
+        match fs::remove_dir_all(&self.dir) {
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(()),
+            r => r,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fnv1a_reference() {
+        assert_eq!(fnv1a(b""), 0xcbf29ce484222325);
+        assert_eq!(fnv1a(b"a"), 0xaf63dc4c8601ec8c);
+    }
+
+    #[test]
+    fn round_trip() {
+        let cache = Cache::at(
+            std::env::temp_dir().join(format!("aoc2024-cache-test-{}", std::process::id())),
+        );
+        let params = Params::from_iter([("word", "MAS")]);
+        let key = Key::new(4, Part::PartOne, "XMAS", &params, &[], 1).unwrap();
+
+        assert_eq!(cache.get(&key).unwrap(), None);
+        cache.put(&key, "38").unwrap();
+        assert_eq!(cache.get(&key).unwrap().as_deref(), Some("38"));
+        // with no temporary file left behind
+        let dir = cache.dir().join(key.path()).parent().unwrap().to_path_buf();
+        assert_eq!(fs::read_dir(dir).unwrap().count(), 1);
+
+        // anything else changing is a miss
+        for other in [
+            Key::new(5, Part::PartOne, "XMAS", &params, &[], 1),
+            Key::new(4, Part::PartTwo, "XMAS", &params, &[], 1),
+            Key::new(4, Part::PartOne, "XMAS\n", &params, &[], 1),
+            Key::new(4, Part::PartOne, "XMAS", &Params::default(), &[], 1),
+            Key::new(4, Part::PartOne, "XMAS", &params, &[], 2),
+        ] {
+            assert_eq!(cache.get(&other.unwrap()).unwrap(), None);
+        }
+
+        cache.clear().unwrap();
+        assert_eq!(cache.get(&key).unwrap(), None);
+        cache.clear().unwrap();
+    }
+
+    #[test]
+    fn file_params() {
+        const DICTIONARY: ParamSpec = ParamSpec {
+            name: "dictionary",
+            default: "",
+            help: "",
+            file: true,
+        };
+        let path = std::env::temp_dir().join(format!("aoc2024-cache-dict-{}", std::process::id()));
+        let params = Params::from_iter([("dictionary", path.to_str().unwrap())]);
+        let key = |params: &Params| {
+            Key::new(4, Part::Extra("words"), "XMAS", params, &[DICTIONARY], 1)
+                .map(|key| key.path())
+        };
+
+        // unset is fine, but a missing file means we can't cache
+        assert!(key(&Params::default()).is_ok());
+        assert!(key(&params).is_err());
+
+        fs::write(&path, "XMAS\n").unwrap();
+        let before = key(&params).unwrap();
+        assert_eq!(key(&params).unwrap(), before);
+        fs::write(&path, "XMAS\nSAMX\n").unwrap();
+        assert_ne!(key(&params).unwrap(), before);
+
+        fs::remove_file(&path).unwrap();
+    }
+}
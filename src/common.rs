@@ -1,13 +1,42 @@
-use std::{error::Error, str::FromStr};
+use std::{
+    collections::HashMap,
+    error::Error,
+    fmt::{self, Display},
+    io::Read,
+    str::FromStr,
+    sync::Mutex,
+};
 
 use thiserror::Error;
 
-pub(crate) type DaySolver = dyn Fn(&str, Part) -> Result<String, Box<dyn Error>>;
+pub(crate) type DaySolver = fn(&str, Part, &Context) -> Result<String, Box<dyn Error>>;
+
+/// A solver that can consume its input incrementally, for inputs too big to
+/// read into memory first.
+pub(crate) type StreamSolver = fn(&mut dyn Read, Part, &Context) -> Result<String, Box<dyn Error>>;
+
+pub(crate) struct Day {
+    pub solver: DaySolver,
+    pub stream_solver: Option<StreamSolver>,
+    pub params: &'static [ParamSpec],
+    pub extra_parts: &'static [PartSpec],
+}
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[allow(clippy::enum_variant_names)]
 pub(crate) enum Part {
     PartOne,
     PartTwo,
+    /// Anything a day can do beyond the puzzle itself (analysis, debugging
+    /// output...), named by one of the day's `PartSpec`s.
+    Extra(&'static str),
+}
+
+/// An extra part declared by a solver, run as e.g. `aoc2024 2 explain input.txt`.
+#[derive(Debug)]
+pub(crate) struct PartSpec {
+    pub name: &'static str,
+    pub help: &'static str,
 }
 
 #[derive(Error, Debug)]
@@ -29,14 +58,204 @@ impl FromStr for Part {
 }
 
 impl Part {
-    pub fn choose<F, G, A, R>(&self, part_one: F, part_two: G, args: A) -> R
+    /// Like `from_str`, but also accepts any of the given extra parts.
+    pub fn parse(s: &str, extra_parts: &'static [PartSpec]) -> Result<Self, PartParseError> {
+        s.parse().or_else(|e| {
+            extra_parts
+                .iter()
+                .find(|spec| spec.name == s)
+                .map(|spec| Self::Extra(spec.name))
+                .ok_or(e)
+        })
+    }
+
+    /// The error for a solver handed an extra part it doesn't have.
+    pub fn unsupported(&self) -> Box<dyn Error> {
+        Box::new(PartParseError::Unknown(self.to_string()))
+    }
+}
+
+impl Display for Part {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Part::PartOne => write!(f, "one"),
+            Part::PartTwo => write!(f, "two"),
+            Part::Extra(name) => write!(f, "{name}"),
+        }
+    }
+}
+
+/// A tunable constant declared by a solver. The default is used unless the
+/// caller overrides it with `--param name=value` (or from a test).
+#[derive(Debug)]
+pub(crate) struct ParamSpec {
+    pub name: &'static str,
+    pub default: &'static str,
+    pub help: &'static str,
+    /// The value is a path to a file the answer depends on, so the cache
+    /// needs to look at its contents and not just the name.
+    pub file: bool,
+}
+
+#[derive(Error, Debug)]
+pub(crate) enum ParamError {
+    #[error("Expected key=value, got {0}")]
+    Malformed(String),
+    #[error("Unknown parameter {0}")]
+    Unknown(String),
+    #[error("Invalid value {value:?} for parameter {name}: {reason}")]
+    Invalid {
+        name: &'static str,
+        value: String,
+        reason: String,
+    },
+}
+
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Params {
+    values: HashMap<String, String>,
+}
+
+impl Params {
+    /// Parses a single `key=value` assignment, as passed to `--param`.
+    pub fn parse_assignment(s: &str) -> Result<(String, String), ParamError> {
+        s.split_once('=')
+            .map(|(k, v)| (k.trim().to_string(), v.to_string()))
+            .ok_or_else(|| ParamError::Malformed(s.to_string()))
+    }
+
+    /// Rejects any parameter that the solver didn't declare, so typos don't
+    /// silently fall back to the default.
+    pub fn check(&self, specs: &[ParamSpec]) -> Result<(), ParamError> {
+        for key in self.values.keys() {
+            if !specs.iter().any(|spec| spec.name == key) {
+                return Err(ParamError::Unknown(key.clone()));
+            }
+        }
+        Ok(())
+    }
+
+    /// The explicitly set parameters, sorted by name.
+    pub fn assignments(&self) -> Vec<(&str, &str)> {
+        let mut assignments = self
+            .values
+            .iter()
+            .map(|(k, v)| (k.as_str(), v.as_str()))
+            .collect::<Vec<_>>();
+        assignments.sort();
+        assignments
+    }
+
+    /// Returns the value only if it was explicitly set, for parameters whose
+    /// default depends on something else (usually the part).
+    pub fn get_explicit<T>(&self, spec: &ParamSpec) -> Result<Option<T>, ParamError>
     where
-        F: FnOnce(A) -> R,
-        G: FnOnce(A) -> R,
+        T: FromStr,
+        T::Err: Display,
     {
-        match self {
-            Part::PartOne => part_one(args),
-            Part::PartTwo => part_two(args),
+        self.values
+            .get(spec.name)
+            .map(|value| {
+                value.parse().map_err(|e: T::Err| ParamError::Invalid {
+                    name: spec.name,
+                    value: value.clone(),
+                    reason: e.to_string(),
+                })
+            })
+            .transpose()
+    }
+
+    pub fn get<T>(&self, spec: &ParamSpec) -> Result<T, ParamError>
+    where
+        T: FromStr,
+        T::Err: Display,
+    {
+        match self.get_explicit(spec)? {
+            Some(value) => Ok(value),
+            None => spec
+                .default
+                .parse()
+                .map_err(|e: T::Err| ParamError::Invalid {
+                    name: spec.name,
+                    value: spec.default.to_string(),
+                    reason: e.to_string(),
+                }),
+        }
+    }
+}
+
+impl<K: Into<String>, V: Into<String>> FromIterator<(K, V)> for Params {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        Self {
+            values: iter
+                .into_iter()
+                .map(|(k, v)| (k.into(), v.into()))
+                .collect(),
+        }
+    }
+}
+
+/// Everything a solver gets besides the input and part: its parameters, and
+/// somewhere to send debug output.
+#[derive(Debug, Default)]
+pub(crate) struct Context {
+    pub params: Params,
+    verbosity: u8,
+    captured: Option<Mutex<Vec<String>>>,
+}
+
+impl Context {
+    /// Logs go to stderr; `verbosity` is the number of `-v`s given.
+    pub fn new(params: Params, verbosity: u8) -> Self {
+        Self {
+            params,
+            verbosity,
+            captured: None,
+        }
+    }
+
+    #[cfg(test)]
+    pub fn with_params(params: Params) -> Self {
+        Self::new(params, 0)
+    }
+
+    /// Keeps log lines (at up to `verbosity`) for `captured()` instead of
+    /// printing them, for tests.
+    #[cfg(test)]
+    pub fn capturing(verbosity: u8) -> Self {
+        Self {
+            params: Params::default(),
+            verbosity,
+            captured: Some(Mutex::new(Vec::new())),
         }
     }
+
+    #[cfg(test)]
+    pub fn captured(&self) -> Vec<String> {
+        self.captured
+            .as_ref()
+            .map(|lines| lines.lock().unwrap().clone())
+            .unwrap_or_default()
+    }
+
+    fn log(&self, level: u8, args: fmt::Arguments) {
+        if self.verbosity < level {
+            return;
+        }
+
+        match &self.captured {
+            Some(lines) => lines.lock().unwrap().push(args.to_string()),
+            None => eprintln!("{args}"),
+        }
+    }
+
+    /// Shown with `-v`.
+    pub fn debug(&self, args: fmt::Arguments) {
+        self.log(1, args)
+    }
+
+    /// Shown with `-vv`.
+    pub fn trace(&self, args: fmt::Arguments) {
+        self.log(2, args)
+    }
 }
@@ -1,8 +1,8 @@
 use std::error::Error;
 
-use crate::common::Part;
+use crate::common::{Context, Part};
 
-pub(crate) fn day_00(input: &str, part: Part) -> Result<String, Box<dyn Error>> {
+pub(crate) fn day_00(_input: &str, _part: Part, _ctx: &Context) -> Result<String, Box<dyn Error>> {
     Ok("".into())
 }
 
@@ -14,19 +14,35 @@ mod tests {
 
     #[test]
     fn example_input() {
-        assert_eq!(&day_00(EXAMPLE_INPUT, Part::PartOne).unwrap(), "");
-        assert_eq!(&day_00(EXAMPLE_INPUT, Part::PartTwo).unwrap(), "");
+        assert_eq!(
+            &day_00(EXAMPLE_INPUT, Part::PartOne, &Context::default()).unwrap(),
+            ""
+        );
+        assert_eq!(
+            &day_00(EXAMPLE_INPUT, Part::PartTwo, &Context::default()).unwrap(),
+            ""
+        );
     }
 
     #[cfg(feature = "regression")]
     #[test]
     fn regression() {
         assert_eq!(
-            &day_00(include_str!("../inputs/00.txt"), Part::PartOne).unwrap(),
+            &day_00(
+                include_str!("../inputs/00.txt"),
+                Part::PartOne,
+                &Context::default()
+            )
+            .unwrap(),
             ""
         );
         assert_eq!(
-            &day_00(include_str!("../inputs/00.txt"), Part::PartTwo).unwrap(),
+            &day_00(
+                include_str!("../inputs/00.txt"),
+                Part::PartTwo,
+                &Context::default()
+            )
+            .unwrap(),
             ""
         );
     }
@@ -1,8 +1,8 @@
 use std::{collections::BTreeMap, error::Error};
 
-use crate::common::Part;
+use crate::common::{Context, Part};
 
-pub(crate) fn day_01(input: &str, part: Part) -> Result<String, Box<dyn Error>> {
+pub(crate) fn day_01(input: &str, part: Part, _ctx: &Context) -> Result<String, Box<dyn Error>> {
     let mut left: Vec<u32> = Vec::new();
     let mut right: Vec<u32> = Vec::new();
 
@@ -36,6 +36,7 @@ pub(crate) fn day_01(input: &str, part: Part) -> Result<String, Box<dyn Error>>
                 .sum::<u32>()
                 .to_string())
         }
+        Part::Extra(_) => Err(part.unsupported()),
     }
 }
 
@@ -52,19 +53,35 @@ mod tests {
 
     #[test]
     fn example_input() {
-        assert_eq!(&day_01(EXAMPLE_INPUT, Part::PartOne).unwrap(), "11");
-        assert_eq!(&day_01(EXAMPLE_INPUT, Part::PartTwo).unwrap(), "31");
+        assert_eq!(
+            &day_01(EXAMPLE_INPUT, Part::PartOne, &Context::default()).unwrap(),
+            "11"
+        );
+        assert_eq!(
+            &day_01(EXAMPLE_INPUT, Part::PartTwo, &Context::default()).unwrap(),
+            "31"
+        );
     }
 
     #[cfg(feature = "regression")]
     #[test]
     fn regression() {
         assert_eq!(
-            &day_01(include_str!("../inputs/01.txt"), Part::PartOne).unwrap(),
+            &day_01(
+                include_str!("../inputs/01.txt"),
+                Part::PartOne,
+                &Context::default()
+            )
+            .unwrap(),
             "1603498"
         );
         assert_eq!(
-            &day_01(include_str!("../inputs/01.txt"), Part::PartTwo).unwrap(),
+            &day_01(
+                include_str!("../inputs/01.txt"),
+                Part::PartTwo,
+                &Context::default()
+            )
+            .unwrap(),
             "25574739"
         );
     }
@@ -1,66 +1,400 @@
-use std::error::Error;
+use std::{error::Error, ops::RangeInclusive, str::FromStr};
 
 use itertools::Itertools;
 
-use crate::common::Part;
+use crate::common::{Context, ParamError, ParamSpec, Params, Part, PartSpec};
 
-fn report_safe(report: &[i32]) -> bool {
-    // monotonicity check. monotonicity is sufficient here (even though it will allow
-    // differences of 0) since we'll do the differences in the next step
-    let orderings = report
-        .iter()
-        .tuple_windows()
-        .map(|(a, b)| a.cmp(b))
-        .collect::<Vec<_>>();
+const MIN_STEP: ParamSpec = ParamSpec {
+    name: "min_step",
+    default: "1",
+    help: "smallest allowed difference between adjacent levels",
+    file: false,
+};
+
+const MAX_STEP: ParamSpec = ParamSpec {
+    name: "max_step",
+    default: "3",
+    help: "largest allowed difference between adjacent levels",
+    file: false,
+};
+
+const ALLOW_EQUAL: ParamSpec = ParamSpec {
+    name: "allow_equal",
+    default: "false",
+    help: "whether adjacent levels may be equal",
+    file: false,
+};
+
+const DIRECTION: ParamSpec = ParamSpec {
+    name: "direction",
+    default: "either",
+    help: "direction a safe report must go in: either, increasing or decreasing",
+    file: false,
+};
+
+// a param rather than its own --tolerance flag, since flags are shared by
+// every day. The default is never parsed, `day_02` picks one per part.
+const TOLERANCE: ParamSpec = ParamSpec {
+    name: "tolerance",
+    default: "per part",
+    help: "number of levels the Problem Dampener may remove: 0 for part one, 1 for part two and unlimited for the removals part unless set",
+    file: false,
+};
+
+pub(crate) const PARAMS: &[ParamSpec] = &[MIN_STEP, MAX_STEP, ALLOW_EQUAL, DIRECTION, TOLERANCE];
+
+pub(crate) const EXTRA_PARTS: &[PartSpec] = &[
+    PartSpec {
+        name: "explain",
+        help: "list each report with why it is or isn't safe, and what the dampener removed",
+    },
+    PartSpec {
+        name: "removals",
+        help: "list the fewest levels each report needs removed to be safe",
+    },
+    PartSpec {
+        name: "longest",
+        help: "find the longest safe subsequence of each report, and the levels to drop for it",
+    },
+];
 
-    if !orderings.iter().all_equal() {
-        return false;
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Direction {
+    Either,
+    Increasing,
+    Decreasing,
+}
+
+impl FromStr for Direction {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "either" => Ok(Self::Either),
+            "increasing" => Ok(Self::Increasing),
+            "decreasing" => Ok(Self::Decreasing),
+            _ => Err(format!(
+                "expected either, increasing or decreasing, got {s}"
+            )),
+        }
     }
+}
 
-    // rate check. symmetric differences must be within [1, 3]
-    report
-        .iter()
-        .tuple_windows()
-        .map(|(a, b)| a.abs_diff(*b))
-        .all(|diff| (1..=3).contains(&diff))
+/// What makes a report safe. The puzzle's rules are the default: strictly
+/// increasing or strictly decreasing, with every step in [1, 3].
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct ReportPolicy {
+    steps: RangeInclusive<u32>,
+    /// Equal neighbours are allowed regardless of `steps`, and don't count
+    /// towards either direction.
+    allow_equal: bool,
+    direction: Direction,
 }
 
-// stupid O(n * m^2) solution...
-// on the other hand it still runs pretty fast. who's to say
+impl Default for ReportPolicy {
+    fn default() -> Self {
+        Self {
+            steps: 1..=3,
+            allow_equal: false,
+            direction: Direction::Either,
+        }
+    }
+}
 
-fn report_variants(report: &[i32]) -> Vec<Vec<i32>> {
-    let mut res = Vec::new();
+impl ReportPolicy {
+    fn from_params(params: &Params) -> Result<Self, ParamError> {
+        Ok(Self {
+            steps: params.get(&MIN_STEP)?..=params.get(&MAX_STEP)?,
+            allow_equal: params.get(&ALLOW_EQUAL)?,
+            direction: params.get(&DIRECTION)?,
+        })
+    }
 
-    for i in 0..report.len() {
-        let mut entry = Vec::from(report);
-        entry.remove(i);
-        res.push(entry);
+    /// The directions a safe report may go in, as "is it increasing".
+    fn directions(&self) -> &'static [bool] {
+        match self.direction {
+            Direction::Either => &[true, false],
+            Direction::Increasing => &[true],
+            Direction::Decreasing => &[false],
+        }
     }
 
-    res
+    fn step_ok(&self, a: i32, b: i32, increasing: bool) -> bool {
+        if a == b {
+            return self.allow_equal;
+        }
+
+        (increasing == (a < b)) && self.steps.contains(&a.abs_diff(b))
+    }
+}
+
+fn report_safe(report: &[i32], policy: &ReportPolicy) -> bool {
+    // every step has to agree with the same direction (so a report that only ever
+    // has equal neighbours is fine in either)
+    policy.directions().iter().any(|&increasing| {
+        report
+            .iter()
+            .tuple_windows()
+            .all(|(a, b)| policy.step_ok(*a, *b, increasing))
+    })
 }
 
-fn report_safe_pt2(report: &[i32]) -> bool {
-    if report_safe(report) {
+/// The fewest levels (as indices into `report`) that have to be removed to make
+/// it safe, or `None` if that's more than `tolerance`.
+///
+/// For each direction, `removed[i]` is the fewest removals among the first `i + 1`
+/// levels that leave level `i` as the last one kept (and which level was kept
+/// before it - ties go to the later one, so removals happen as early as possible).
+/// Level `i` can only follow one of the `tolerance + 1` levels before it (anything
+/// further back would skip too many), so this is O(n * k) instead of trying every
+/// variant.
+fn dampen(report: &[i32], policy: &ReportPolicy, tolerance: usize) -> Option<Vec<usize>> {
+    if report_safe(report, policy) {
         // can early exit here since removing no entries is a solution
-        return true;
+        return Some(Vec::new());
+    }
+
+    let n = report.len();
+    // can't remove more than all of them, and keeps `tolerance + 1` in range
+    let tolerance = tolerance.min(n);
+    let mut best: Option<(usize, Vec<usize>)> = None;
+
+    for &increasing in policy.directions() {
+        let mut removed: Vec<Option<(usize, Option<usize>)>> = vec![None; n];
+        for i in 0..n {
+            // start the kept levels at i, dropping everything before it
+            let mut here = (i <= tolerance).then_some((i, None));
+
+            for j in i.saturating_sub(tolerance + 1)..i {
+                if let Some((before, _)) = removed[j] {
+                    let total = before + (i - j - 1);
+                    if total <= tolerance
+                        && here.is_none_or(|(h, _)| total <= h)
+                        && policy.step_ok(report[j], report[i], increasing)
+                    {
+                        here = Some((total, Some(j)));
+                    }
+                }
+            }
+
+            removed[i] = here;
+        }
+
+        // and drop everything after the last kept level
+        for (i, entry) in removed.iter().enumerate() {
+            let Some((total, _)) = entry.map(|(r, prev)| (r + (n - 1 - i), prev)) else {
+                continue;
+            };
+
+            if total <= tolerance && best.as_ref().is_none_or(|(b, _)| total <= *b) {
+                let mut kept = vec![i];
+                while let Some((_, Some(prev))) = removed[*kept.last().unwrap()] {
+                    kept.push(prev);
+                }
+                best = Some((total, kept));
+            }
+        }
+    }
+
+    best.map(|(_, kept)| {
+        let mut keep = vec![false; n];
+        for i in kept {
+            keep[i] = true;
+        }
+        (0..n).filter(|&i| !keep[i]).collect()
+    })
+}
+
+fn min_removals(report: &[i32], policy: &ReportPolicy, tolerance: usize) -> Option<usize> {
+    dampen(report, policy, tolerance).map(|removed| removed.len())
+}
+
+/// The levels to drop (by index) to get the longest subsequence of `report` that
+/// is safe on its own. With as many removals allowed as there are levels, the
+/// window in `dampen` covers every earlier level, which makes it the O(n^2)
+/// longest-chain DP.
+fn longest_safe(report: &[i32], policy: &ReportPolicy) -> Vec<usize> {
+    // a single level (or none at all) is always safe, so this can't fail
+    dampen(report, policy, report.len()).unwrap()
+}
+
+fn describe_longest(report: &[i32], policy: &ReportPolicy) -> String {
+    let levels = report.iter().join(" ");
+    let dropped = longest_safe(report, policy);
+    if dropped.is_empty() {
+        return format!("{levels}: safe, keep all {}", report.len());
     }
 
-    report_variants(report)
+    let kept = (0..report.len())
+        .filter(|i| !dropped.contains(i))
+        .map(|i| report[i])
+        .collect::<Vec<_>>();
+    format!(
+        "{levels}: keep {} ({} of {}), drop level(s) {}",
+        kept.iter().join(" "),
+        kept.len(),
+        report.len(),
+        dropped
+            .iter()
+            .map(|i| format!("{i} ({})", report[*i]))
+            .join(", ")
+    )
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum Violation {
+    /// The pair goes the wrong way - against the report's direction so far, or
+    /// against the policy's only allowed direction.
+    Direction {
+        increasing: bool,
+    },
+    Step {
+        step: u32,
+    },
+    Equal,
+}
+
+/// The first adjacent pair (by index of its first level) that makes `report`
+/// unsafe, and why.
+fn first_violation(report: &[i32], policy: &ReportPolicy) -> Option<(usize, Violation)> {
+    // with either direction allowed, the first pair that goes anywhere decides it
+    let increasing = match policy.direction {
+        Direction::Either => report
+            .iter()
+            .tuple_windows()
+            .find(|(a, b)| a != b)
+            .is_none_or(|(a, b)| a < b),
+        Direction::Increasing => true,
+        Direction::Decreasing => false,
+    };
+
+    report
         .iter()
-        .any(|report| report_safe(report))
+        .tuple_windows()
+        .position(|(a, b)| !policy.step_ok(*a, *b, increasing))
+        .map(|i| {
+            let (a, b) = (report[i], report[i + 1]);
+            let violation = if a == b {
+                Violation::Equal
+            } else if (a < b) != increasing {
+                Violation::Direction { increasing }
+            } else {
+                Violation::Step {
+                    step: a.abs_diff(b),
+                }
+            };
+            (i, violation)
+        })
 }
 
-pub(crate) fn day_02(input: &str, part: Part) -> Result<String, Box<dyn Error>> {
+fn explain(report: &[i32], policy: &ReportPolicy, tolerance: usize) -> String {
+    let levels = report.iter().join(" ");
+    let Some((i, violation)) = first_violation(report, policy) else {
+        return format!("{levels}: safe");
+    };
+
+    let (a, b) = (report[i], report[i + 1]);
+    let reason = match violation {
+        Violation::Direction { increasing } => format!(
+            "direction change, expected {}",
+            if increasing {
+                "increasing"
+            } else {
+                "decreasing"
+            }
+        ),
+        Violation::Step { step } => format!(
+            "step of {step} outside {}..={}",
+            policy.steps.start(),
+            policy.steps.end()
+        ),
+        Violation::Equal => "equal levels".into(),
+    };
+    let mut explanation = format!(
+        "{levels}: unsafe at levels {i} and {} ({a} -> {b}): {reason}",
+        i + 1
+    );
+
+    if tolerance > 0 {
+        match dampen(report, policy, tolerance) {
+            Some(removed) => {
+                explanation += &format!(
+                    "; safe after removing level(s) {}",
+                    removed
+                        .iter()
+                        .map(|i| format!("{i} ({})", report[*i]))
+                        .join(", ")
+                )
+            }
+            None => {
+                explanation += &format!("; still unsafe after removing up to {tolerance} level(s)")
+            }
+        }
+    }
+
+    explanation
+}
+
+pub(crate) fn day_02(input: &str, part: Part, ctx: &Context) -> Result<String, Box<dyn Error>> {
+    let policy = ReportPolicy::from_params(&ctx.params)?;
+
     let reports = input
         .lines()
         .map(|line| line.split_whitespace().map(|col| col.parse()).collect())
         .collect::<Result<Vec<Vec<i32>>, _>>()?;
 
+    let tolerance = match ctx.params.get_explicit(&TOLERANCE)? {
+        Some(tolerance) => tolerance,
+        None => match part {
+            Part::PartOne => 0,
+            Part::Extra("removals") => usize::MAX,
+            Part::PartTwo | Part::Extra(_) => 1,
+        },
+    };
+
+    match part {
+        Part::Extra("explain") => {
+            return Ok(reports
+                .iter()
+                .map(|report| explain(report, &policy, tolerance))
+                .join("\n"))
+        }
+        Part::Extra("removals") => {
+            return Ok(reports
+                .iter()
+                .map(|report| {
+                    let levels = report.iter().join(" ");
+                    match min_removals(report, &policy, tolerance) {
+                        Some(removals) => format!("{levels}: {removals} removal(s)"),
+                        None => format!("{levels}: more than {tolerance} removal(s)"),
+                    }
+                })
+                .join("\n"));
+        }
+        Part::Extra("longest") => {
+            return Ok(reports
+                .iter()
+                .map(|report| describe_longest(report, &policy))
+                .join("\n"))
+        }
+        _ => {}
+    }
+
     Ok(reports
         .iter()
-        .map(|report| part.choose(report_safe, report_safe_pt2, report))
-        .filter(|x| *x)
+        .map(|report| {
+            let removals = min_removals(report, &policy, tolerance);
+            match removals {
+                Some(removals) => {
+                    ctx.debug(format_args!("{report:?}: safe with {removals} removal(s)"))
+                }
+                None => ctx.debug(format_args!(
+                    "{report:?}: needs more than {tolerance} removal(s)"
+                )),
+            }
+            removals
+        })
+        .filter(|x| x.is_some())
         .count()
         .to_string())
 }
@@ -78,36 +412,288 @@ mod tests {
 
     #[test]
     fn example_input() {
-        assert_eq!(&day_02(EXAMPLE_INPUT, Part::PartOne).unwrap(), "2");
-        assert_eq!(&day_02(EXAMPLE_INPUT, Part::PartTwo).unwrap(), "4");
+        assert_eq!(
+            &day_02(EXAMPLE_INPUT, Part::PartOne, &Context::default()).unwrap(),
+            "2"
+        );
+        assert_eq!(
+            &day_02(EXAMPLE_INPUT, Part::PartTwo, &Context::default()).unwrap(),
+            "4"
+        );
     }
 
     #[test]
     fn example_rows() {
-        assert!(report_safe(&[7, 6, 4, 2, 1]));
-        assert!(!report_safe(&[1, 2, 7, 8, 9]));
-        assert!(!report_safe(&[9, 7, 6, 2, 1]));
-        assert!(!report_safe(&[1, 3, 2, 4, 5]));
-        assert!(!report_safe(&[8, 6, 4, 4, 1]));
-        assert!(report_safe(&[1, 3, 6, 7, 9]));
+        assert!(report_safe(&[7, 6, 4, 2, 1], &ReportPolicy::default()));
+        assert!(!report_safe(&[1, 2, 7, 8, 9], &ReportPolicy::default()));
+        assert!(!report_safe(&[9, 7, 6, 2, 1], &ReportPolicy::default()));
+        assert!(!report_safe(&[1, 3, 2, 4, 5], &ReportPolicy::default()));
+        assert!(!report_safe(&[8, 6, 4, 4, 1], &ReportPolicy::default()));
+        assert!(report_safe(&[1, 3, 6, 7, 9], &ReportPolicy::default()));
+
+        assert_eq!(
+            min_removals(&[7, 6, 4, 2, 1], &ReportPolicy::default(), 1),
+            Some(0)
+        );
+        assert_eq!(
+            min_removals(&[1, 2, 7, 8, 9], &ReportPolicy::default(), 1),
+            None
+        );
+        assert_eq!(
+            min_removals(&[9, 7, 6, 2, 1], &ReportPolicy::default(), 1),
+            None
+        );
+        assert_eq!(
+            min_removals(&[1, 3, 2, 4, 5], &ReportPolicy::default(), 1),
+            Some(1)
+        );
+        assert_eq!(
+            min_removals(&[8, 6, 4, 4, 1], &ReportPolicy::default(), 1),
+            Some(1)
+        );
+        assert_eq!(
+            min_removals(&[1, 3, 6, 7, 9], &ReportPolicy::default(), 1),
+            Some(0)
+        );
+    }
+
+    #[test]
+    fn example_input_params() {
+        // same reports, but only steps of exactly 1 or 2 are allowed
+        let ctx = Context::with_params(Params::from_iter([("min_step", "1"), ("max_step", "2")]));
+        assert_eq!(&day_02(EXAMPLE_INPUT, Part::PartOne, &ctx).unwrap(), "1");
+        assert_eq!(&day_02(EXAMPLE_INPUT, Part::PartTwo, &ctx).unwrap(), "2");
+    }
+
+    #[test]
+    fn min_removals_tolerance() {
+        assert_eq!(
+            min_removals(&[1, 2, 7, 8, 9], &ReportPolicy::default(), 5),
+            Some(2)
+        );
+        assert_eq!(
+            min_removals(&[9, 7, 6, 2, 1], &ReportPolicy::default(), 5),
+            Some(2)
+        );
+        // first and last levels are the bad ones
+        assert_eq!(
+            min_removals(&[20, 1, 2, 3, 4, 0], &ReportPolicy::default(), 2),
+            Some(2)
+        );
+        assert_eq!(
+            min_removals(&[20, 1, 2, 3, 4, 0], &ReportPolicy::default(), 1),
+            None
+        );
+        // two bad levels in a row in the middle
+        assert_eq!(
+            min_removals(&[1, 2, 9, 9, 3, 4], &ReportPolicy::default(), 2),
+            Some(2)
+        );
+        assert_eq!(
+            min_removals(&[5, 5, 5, 5], &ReportPolicy::default(), 5),
+            Some(3)
+        );
+        assert_eq!(min_removals(&[], &ReportPolicy::default(), 0), Some(0));
+    }
+
+    #[test]
+    fn min_removals_matches_brute_force() {
+        fn brute_force(report: &[i32]) -> usize {
+            (0..=report.len())
+                .find(|&k| {
+                    report
+                        .iter()
+                        .copied()
+                        .combinations(report.len() - k)
+                        .any(|kept| report_safe(&kept, &ReportPolicy::default()))
+                })
+                .unwrap()
+        }
+
+        // every report of length 6 over a small alphabet of levels
+        for report in (0..6).map(|_| [1, 2, 4, 7]).multi_cartesian_product() {
+            let expected = brute_force(&report);
+            for tolerance in 0..=report.len() {
+                assert_eq!(
+                    min_removals(&report, &ReportPolicy::default(), tolerance),
+                    (expected <= tolerance).then_some(expected),
+                    "{report:?} with tolerance {tolerance}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn example_rows_policies() {
+        let increasing = ReportPolicy {
+            direction: Direction::Increasing,
+            ..Default::default()
+        };
+        assert!(!report_safe(&[7, 6, 4, 2, 1], &increasing));
+        assert!(report_safe(&[1, 3, 6, 7, 9], &increasing));
+        assert_eq!(min_removals(&[9, 7, 6, 2, 1], &increasing, 5), Some(4));
+
+        let allow_equal = ReportPolicy {
+            allow_equal: true,
+            ..Default::default()
+        };
+        assert!(report_safe(&[8, 6, 4, 4, 1], &allow_equal));
+        assert!(report_safe(&[3, 3, 3], &allow_equal));
+        assert!(!report_safe(&[1, 3, 2, 4, 5], &allow_equal));
+
+        let wide = ReportPolicy {
+            steps: 1..=5,
+            ..Default::default()
+        };
+        assert!(report_safe(&[1, 2, 7, 8, 9], &wide));
+        assert!(!report_safe(
+            &[9, 7, 6, 2, 1],
+            &ReportPolicy {
+                steps: 2..=4,
+                ..wide
+            }
+        ));
+    }
+
+    #[test]
+    fn example_input_policies() {
+        let ctx = Context::with_params(Params::from_iter([("direction", "decreasing")]));
+        assert_eq!(&day_02(EXAMPLE_INPUT, Part::PartOne, &ctx).unwrap(), "1");
+        assert_eq!(&day_02(EXAMPLE_INPUT, Part::PartTwo, &ctx).unwrap(), "2");
+
+        let ctx = Context::with_params(Params::from_iter([("allow_equal", "true")]));
+        assert_eq!(&day_02(EXAMPLE_INPUT, Part::PartOne, &ctx).unwrap(), "3");
+        assert_eq!(&day_02(EXAMPLE_INPUT, Part::PartTwo, &ctx).unwrap(), "4");
+
+        let ctx = Context::with_params(Params::from_iter([("direction", "sideways")]));
+        assert!(day_02(EXAMPLE_INPUT, Part::PartOne, &ctx).is_err());
+    }
+
+    #[test]
+    fn example_input_explain() {
+        assert_eq!(
+            day_02(EXAMPLE_INPUT, Part::Extra("explain"), &Context::default())
+                .unwrap()
+                .lines()
+                .collect::<Vec<_>>(),
+            vec![
+                "7 6 4 2 1: safe",
+                "1 2 7 8 9: unsafe at levels 1 and 2 (2 -> 7): step of 5 outside 1..=3; still unsafe after removing up to 1 level(s)",
+                "9 7 6 2 1: unsafe at levels 2 and 3 (6 -> 2): step of 4 outside 1..=3; still unsafe after removing up to 1 level(s)",
+                "1 3 2 4 5: unsafe at levels 1 and 2 (3 -> 2): direction change, expected increasing; safe after removing level(s) 1 (3)",
+                "8 6 4 4 1: unsafe at levels 2 and 3 (4 -> 4): equal levels; safe after removing level(s) 2 (4)",
+                "1 3 6 7 9: safe",
+            ]
+        );
+    }
+
+    #[test]
+    fn explain_policies() {
+        let increasing = ReportPolicy {
+            direction: Direction::Increasing,
+            ..Default::default()
+        };
+        assert_eq!(
+            explain(&[7, 6, 4, 2, 1], &increasing, 0),
+            "7 6 4 2 1: unsafe at levels 0 and 1 (7 -> 6): direction change, expected increasing"
+        );
+        assert_eq!(
+            explain(&[9, 1, 2, 3, 10], &increasing, 2),
+            "9 1 2 3 10: unsafe at levels 0 and 1 (9 -> 1): direction change, expected increasing; safe after removing level(s) 0 (9), 4 (10)"
+        );
+    }
 
-        assert!(report_safe_pt2(&[7, 6, 4, 2, 1]));
-        assert!(!report_safe_pt2(&[1, 2, 7, 8, 9]));
-        assert!(!report_safe_pt2(&[9, 7, 6, 2, 1]));
-        assert!(report_safe_pt2(&[1, 3, 2, 4, 5]));
-        assert!(report_safe_pt2(&[8, 6, 4, 4, 1]));
-        assert!(report_safe_pt2(&[1, 3, 6, 7, 9]));
+    #[test]
+    fn example_input_removals() {
+        assert_eq!(
+            day_02(EXAMPLE_INPUT, Part::Extra("removals"), &Context::default())
+                .unwrap()
+                .lines()
+                .collect::<Vec<_>>(),
+            vec![
+                "7 6 4 2 1: 0 removal(s)",
+                "1 2 7 8 9: 2 removal(s)",
+                "9 7 6 2 1: 2 removal(s)",
+                "1 3 2 4 5: 1 removal(s)",
+                "8 6 4 4 1: 1 removal(s)",
+                "1 3 6 7 9: 0 removal(s)",
+            ]
+        );
+
+        let ctx = Context::with_params(Params::from_iter([("tolerance", "1")]));
+        assert_eq!(
+            day_02("1 2 7 8 9", Part::Extra("removals"), &ctx).unwrap(),
+            "1 2 7 8 9: more than 1 removal(s)"
+        );
+    }
+
+    #[test]
+    fn example_input_longest() {
+        assert_eq!(
+            day_02(EXAMPLE_INPUT, Part::Extra("longest"), &Context::default())
+                .unwrap()
+                .lines()
+                .collect::<Vec<_>>(),
+            vec![
+                "7 6 4 2 1: safe, keep all 5",
+                "1 2 7 8 9: keep 7 8 9 (3 of 5), drop level(s) 0 (1), 1 (2)",
+                "9 7 6 2 1: keep 9 7 6 (3 of 5), drop level(s) 3 (2), 4 (1)",
+                "1 3 2 4 5: keep 1 2 4 5 (4 of 5), drop level(s) 1 (3)",
+                "8 6 4 4 1: keep 8 6 4 1 (4 of 5), drop level(s) 2 (4)",
+                "1 3 6 7 9: safe, keep all 5",
+            ]
+        );
+    }
+
+    #[test]
+    fn longest_safe_long_reports() {
+        let policy = ReportPolicy::default();
+        // the noise is spread out, more than any fixed window would cover
+        let report = [1, 50, 2, 60, 70, 3, 4, 80, 90, 99, 5, 6];
+        assert_eq!(longest_safe(&report, &policy), vec![1, 3, 4, 7, 8, 9]);
+        assert_eq!(longest_safe(&[5, 4, 3, 2, 1, 0], &policy), vec![]);
+        assert_eq!(longest_safe(&[3, 3, 3], &policy).len(), 2);
+        assert_eq!(longest_safe(&[], &policy), vec![]);
+    }
+
+    #[test]
+    fn dampen_removes_fewest() {
+        let policy = ReportPolicy::default();
+        assert_eq!(dampen(&[1, 3, 6, 7, 9], &policy, 1), Some(vec![]));
+        assert_eq!(dampen(&[20, 1, 2, 3, 4, 0], &policy, 2), Some(vec![0, 5]));
+        assert_eq!(dampen(&[1, 2, 9, 9, 3, 4], &policy, 3), Some(vec![2, 3]));
+        assert_eq!(dampen(&[1, 2, 7, 8, 9], &policy, 1), None);
+        assert_eq!(
+            dampen(&[1, 2, 7, 8, 9], &policy, usize::MAX),
+            Some(vec![0, 1])
+        );
+    }
+
+    #[test]
+    fn example_input_tolerance() {
+        let ctx = Context::with_params(Params::from_iter([("tolerance", "2")]));
+        assert_eq!(&day_02(EXAMPLE_INPUT, Part::PartOne, &ctx).unwrap(), "6");
     }
 
     #[cfg(feature = "regression")]
     #[test]
     fn regression() {
         assert_eq!(
-            &day_02(include_str!("../inputs/02.txt"), Part::PartOne).unwrap(),
+            &day_02(
+                include_str!("../inputs/02.txt"),
+                Part::PartOne,
+                &Context::default()
+            )
+            .unwrap(),
             "524"
         );
         assert_eq!(
-            &day_02(include_str!("../inputs/02.txt"), Part::PartTwo).unwrap(),
+            &day_02(
+                include_str!("../inputs/02.txt"),
+                Part::PartTwo,
+                &Context::default()
+            )
+            .unwrap(),
             "569"
         );
     }
@@ -1,48 +1,535 @@
-use std::error::Error;
+use std::{
+    error::Error,
+    fmt::Display,
+    io::{self, Read},
+    ops::Range,
+    str::FromStr,
+};
 
 use itertools::Itertools;
-use regex::Regex;
+use thiserror::Error;
 
-use crate::common::Part;
+use crate::common::{Context, ParamSpec, Part, PartSpec};
 
-pub(crate) fn day_03(input: &str, part: Part) -> Result<String, Box<dyn Error>> {
-    match part {
-        Part::PartOne => {
-            let re = Regex::new(r"mul\((\d+),(\d+)\)").unwrap();
+const INSTRUCTIONS: ParamSpec = ParamSpec {
+    name: "instructions",
+    default: "mul,do,don't",
+    help: "comma-separated instructions to recognise (mul, do, don't, add, sub, reset, enable, disable); part one only has mul unless set explicitly",
+    file: false,
+};
+
+pub(crate) const PARAMS: &[ParamSpec] = &[INSTRUCTIONS];
+
+pub(crate) const EXTRA_PARTS: &[PartSpec] = &[PartSpec {
+    name: "trace",
+    help: "list every instruction (and near miss) with its position, whether it was enabled and the running total",
+}];
+
+const MAX_ARITY: usize = 4;
+
+#[derive(Debug, Default)]
+struct State {
+    enabled: bool,
+    acc: i64,
+}
 
-            let mut ac = 0;
-            for (_, [a, b]) in re.captures_iter(input).map(|c| c.extract()) {
-                ac += a.parse::<u32>()? * b.parse::<u32>()?;
+/// One entry in the instruction set: `name(a,b,...)` with exactly `arity`
+/// operands of 1-3 digits each.
+#[derive(Clone, Copy, Debug)]
+struct Instruction {
+    name: &'static str,
+    arity: usize,
+    effect: fn(&mut State, &[u32]),
+}
+
+/// Everything our corrupted-memory variants know about. The puzzle itself only
+/// has the first three.
+const KNOWN_INSTRUCTIONS: &[Instruction] = &[
+    Instruction {
+        name: "mul",
+        arity: 2,
+        effect: |state, args| {
+            if state.enabled {
+                state.acc += args[0] as i64 * args[1] as i64;
+            }
+        },
+    },
+    Instruction {
+        name: "do",
+        arity: 0,
+        effect: |state, _| state.enabled = true,
+    },
+    Instruction {
+        name: "don't",
+        arity: 0,
+        effect: |state, _| state.enabled = false,
+    },
+    Instruction {
+        name: "add",
+        arity: 2,
+        effect: |state, args| {
+            if state.enabled {
+                state.acc += args[0] as i64 + args[1] as i64;
             }
-            Ok(ac.to_string())
+        },
+    },
+    Instruction {
+        name: "sub",
+        arity: 2,
+        effect: |state, args| {
+            if state.enabled {
+                state.acc += args[0] as i64 - args[1] as i64;
+            }
+        },
+    },
+    Instruction {
+        name: "reset",
+        arity: 0,
+        effect: |state, _| {
+            if state.enabled {
+                state.acc = 0;
+            }
+        },
+    },
+    Instruction {
+        name: "enable",
+        arity: 0,
+        effect: |state, _| state.enabled = true,
+    },
+    Instruction {
+        name: "disable",
+        arity: 0,
+        effect: |state, _| state.enabled = false,
+    },
+];
+
+#[derive(Error, Debug)]
+#[error("Unknown instruction {0:?}")]
+struct UnknownInstruction(String);
+
+struct InstructionSet(Vec<Instruction>);
+
+impl InstructionSet {
+    fn new(instructions: Vec<Instruction>) -> Self {
+        assert!(instructions.iter().all(|i| i.arity <= MAX_ARITY));
+        Self(instructions)
+    }
+}
+
+impl FromStr for InstructionSet {
+    type Err = UnknownInstruction;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.split(',')
+            .map(|name| {
+                KNOWN_INSTRUCTIONS
+                    .iter()
+                    .find(|i| i.name == name.trim())
+                    .copied()
+                    .ok_or_else(|| UnknownInstruction(name.to_string()))
+            })
+            .collect::<Result<_, _>>()
+            .map(Self::new)
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Token<'a> {
+    instruction: &'a Instruction,
+    operands: [u32; MAX_ARITY],
+}
+
+impl Token<'_> {
+    fn operands(&self) -> &[u32] {
+        &self.operands[..self.instruction.arity]
+    }
+}
+
+impl Display for Token<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}({})",
+            self.instruction.name,
+            self.operands().iter().join(",")
+        )
+    }
+}
+
+#[derive(Clone, Debug)]
+struct Spanned<'a> {
+    token: Token<'a>,
+    /// Byte range in the input.
+    span: Range<usize>,
+}
+
+#[derive(Clone, Debug)]
+enum Scanned<'a> {
+    Token(Spanned<'a>),
+    /// Something that started like an instruction but then wasn't one, like
+    /// `mul[3,7]`. The span ends at (and includes) the byte that ruled it out.
+    Skipped {
+        name: &'static str,
+        span: Range<usize>,
+    },
+}
+
+/// Why there's no instruction at some position.
+enum Miss {
+    /// There definitely isn't one, as of the byte at `at`.
+    No { at: usize },
+    /// Not an instruction, but it started out like `name` (see `Scanned::Skipped`).
+    Near { name: &'static str, end: usize },
+    /// There might be, but the buffer ends before we can tell.
+    Incomplete,
+}
+
+/// The part of the input currently in memory. Unless it's the end of the input,
+/// running off the end of it means we need to read more before deciding.
+struct Window<'b> {
+    buf: &'b [u8],
+    eof: bool,
+}
+
+impl Window<'_> {
+    fn ran_out(&self) -> Miss {
+        if self.eof {
+            Miss::No { at: self.buf.len() }
+        } else {
+            Miss::Incomplete
         }
-        Part::PartTwo => {
-            let cond_re = Regex::new(
-                r"(?s:(?:^(.*?)(?:don't\(\)))|(?:(?:do\(\))(.*?)(?:don't\(\)))|(?:(?:do\(\))(.*?)$))",
-            )
-            .unwrap();
+    }
 
-            let x = day_03(
-                &cond_re
-                    .captures_iter(input)
-                    .map(|c| {
-                        c.get(1)
-                            .or_else(|| c.get(2))
-                            .or_else(|| c.get(3))
-                            .unwrap()
-                            .as_str()
-                    })
-                    .join(""),
-                Part::PartOne,
-            );
-            x
+    fn literal(&self, at: usize, literal: &str) -> Result<usize, Miss> {
+        let available = &self.buf[at..];
+        let n = available.len().min(literal.len());
+        if let Some(i) = (0..n).find(|&i| available[i] != literal.as_bytes()[i]) {
+            Err(Miss::No { at: at + i })
+        } else if n < literal.len() {
+            Err(self.ran_out())
+        } else {
+            Ok(at + literal.len())
         }
     }
+
+    /// An operand is 1-3 digits, nothing longer.
+    fn number(&self, at: usize) -> Result<(u32, usize), Miss> {
+        let available = &self.buf[at..];
+        let digits = available
+            .iter()
+            .take(4)
+            .take_while(|c| c.is_ascii_digit())
+            .count();
+        if digits < 4 && digits == available.len() {
+            // the next chunk might have more digits
+            return Err(self.ran_out());
+        }
+        if !(1..=3).contains(&digits) {
+            return Err(Miss::No {
+                at: at + digits.min(3),
+            });
+        }
+
+        let value = available[..digits]
+            .iter()
+            .fold(0, |acc, c| acc * 10 + (c - b'0') as u32);
+        Ok((value, at + digits))
+    }
+
+    fn instruction<'a>(
+        &self,
+        at: usize,
+        instruction: &'a Instruction,
+    ) -> Result<(Token<'a>, usize), Miss> {
+        let at = self.literal(at, instruction.name)?;
+        self.operands(at, instruction).map_err(|miss| match miss {
+            Miss::No { at } => Miss::Near {
+                name: instruction.name,
+                end: (at + 1).min(self.buf.len()),
+            },
+            miss => miss,
+        })
+    }
+
+    /// Everything after the instruction's name.
+    fn operands<'a>(
+        &self,
+        mut at: usize,
+        instruction: &'a Instruction,
+    ) -> Result<(Token<'a>, usize), Miss> {
+        at = self.literal(at, "(")?;
+
+        let mut operands = [0; MAX_ARITY];
+        for (i, operand) in operands.iter_mut().take(instruction.arity).enumerate() {
+            if i > 0 {
+                at = self.literal(at, ",")?;
+            }
+            (*operand, at) = self.number(at)?;
+        }
+
+        at = self.literal(at, ")")?;
+        Ok((
+            Token {
+                instruction,
+                operands,
+            },
+            at,
+        ))
+    }
+
+    fn token_at<'a>(
+        &self,
+        instructions: &'a InstructionSet,
+        at: usize,
+    ) -> Result<(Token<'a>, usize), Miss> {
+        // names can't contain '(', so at most one instruction can match here
+        let mut miss = Miss::No { at };
+        for instruction in &instructions.0 {
+            match self.instruction(at, instruction) {
+                Ok(found) => return Ok(found),
+                Err(Miss::Incomplete) => miss = Miss::Incomplete,
+                Err(near @ Miss::Near { .. }) if !matches!(miss, Miss::Incomplete) => miss = near,
+                Err(_) => {}
+            }
+        }
+        Err(miss)
+    }
+}
+
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/// Pulls the instructions out of corrupted memory, skipping everything else.
+///
+/// The input is read a chunk at a time, and only the unscanned tail of the
+/// buffer (at most one partial instruction) is kept when reading the next, so
+/// memory use doesn't depend on the size of the input.
+struct Scanner<'a, R> {
+    instructions: &'a InstructionSet,
+    reader: R,
+    chunk: Box<[u8]>,
+    buf: Vec<u8>,
+    pos: usize,
+    /// Where `buf[0]` is in the whole input.
+    offset: usize,
+    eof: bool,
+}
+
+impl<'a, R: Read> Scanner<'a, R> {
+    fn new(instructions: &'a InstructionSet, reader: R) -> Self {
+        Self {
+            instructions,
+            reader,
+            chunk: vec![0; CHUNK_SIZE].into_boxed_slice(),
+            buf: Vec::new(),
+            pos: 0,
+            offset: 0,
+            eof: false,
+        }
+    }
+
+    fn fill(&mut self) -> io::Result<()> {
+        self.buf.drain(..self.pos);
+        self.offset += self.pos;
+        self.pos = 0;
+
+        let read = loop {
+            match self.reader.read(&mut self.chunk) {
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                read => break read?,
+            }
+        };
+        self.buf.extend_from_slice(&self.chunk[..read]);
+        self.eof = read == 0;
+        Ok(())
+    }
+}
+
+impl<'a, R: Read> Iterator for Scanner<'a, R> {
+    type Item = io::Result<Scanned<'a>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if self.pos == self.buf.len() && self.eof {
+                return None;
+            }
+
+            let window = Window {
+                buf: &self.buf,
+                eof: self.eof,
+            };
+            let found = if self.pos == self.buf.len() {
+                Err(Miss::Incomplete)
+            } else {
+                window.token_at(self.instructions, self.pos)
+            };
+
+            match found {
+                Ok((token, end)) => {
+                    let span = self.offset + self.pos..self.offset + end;
+                    self.pos = end;
+                    return Some(Ok(Scanned::Token(Spanned { token, span })));
+                }
+                Err(Miss::Near { name, end }) => {
+                    let span = self.offset + self.pos..self.offset + end;
+                    self.pos += 1;
+                    return Some(Ok(Scanned::Skipped { name, span }));
+                }
+                Err(Miss::No { .. }) => self.pos += 1,
+                Err(Miss::Incomplete) => {
+                    if let Err(e) = self.fill() {
+                        return Some(Err(e));
+                    }
+                }
+            }
+        }
+    }
+}
+
+struct Interpreter {
+    state: State,
+}
+
+impl Interpreter {
+    fn new() -> Self {
+        Self {
+            state: State {
+                enabled: true,
+                acc: 0,
+            },
+        }
+    }
+
+    fn step(&mut self, token: &Token) {
+        (token.instruction.effect)(&mut self.state, token.operands());
+    }
+}
+
+fn instruction_set(part: Part, ctx: &Context) -> Result<InstructionSet, Box<dyn Error>> {
+    Ok(match ctx.params.get_explicit(&INSTRUCTIONS)? {
+        Some(instructions) => instructions,
+        None => match part {
+            // no conditionals in part one
+            Part::PartOne => "mul".parse()?,
+            Part::PartTwo | Part::Extra("trace") => ctx.params.get(&INSTRUCTIONS)?,
+            Part::Extra(_) => return Err(part.unsupported()),
+        },
+    })
+}
+
+/// Keeps track of 1-based line and column (in chars) while moving forwards
+/// through the input.
+struct Cursor<'a> {
+    input: &'a str,
+    offset: usize,
+    line: usize,
+    column: usize,
+}
+
+impl<'a> Cursor<'a> {
+    fn new(input: &'a str) -> Self {
+        Self {
+            input,
+            offset: 0,
+            line: 1,
+            column: 1,
+        }
+    }
+
+    fn advance_to(&mut self, offset: usize) -> (usize, usize) {
+        // spans always start on a char boundary, since instructions are ASCII
+        for c in self.input[self.offset..offset].chars() {
+            if c == '\n' {
+                self.line += 1;
+                self.column = 1;
+            } else {
+                self.column += 1;
+            }
+        }
+        self.offset = offset;
+        (self.line, self.column)
+    }
+}
+
+/// Lists what the scanner found, one per line: byte offset, line:column, then
+/// either the instruction with the state it ran in and the total after it, or
+/// the near miss that was skipped.
+fn trace(input: &str, ctx: &Context) -> Result<String, Box<dyn Error>> {
+    let instructions = instruction_set(Part::Extra("trace"), ctx)?;
+
+    let mut interpreter = Interpreter::new();
+    let mut cursor = Cursor::new(input);
+    let mut lines = Vec::new();
+    for scanned in Scanner::new(&instructions, input.as_bytes()) {
+        match scanned? {
+            Scanned::Token(Spanned { token, span }) => {
+                let (line, column) = cursor.advance_to(span.start);
+                let enabled = if interpreter.state.enabled {
+                    "enabled"
+                } else {
+                    "disabled"
+                };
+                interpreter.step(&token);
+                lines.push(format!(
+                    "@{} {line}:{column} {token} {enabled}, total {}",
+                    span.start, interpreter.state.acc
+                ));
+            }
+            Scanned::Skipped { name, span } => {
+                let (line, column) = cursor.advance_to(span.start);
+                lines.push(format!(
+                    "@{} {line}:{column} skipped {:?} (not a valid {name})",
+                    span.start,
+                    String::from_utf8_lossy(&input.as_bytes()[span.clone()])
+                ));
+            }
+        }
+    }
+    lines.push(format!("total {}", interpreter.state.acc));
+
+    Ok(lines.join("\n"))
+}
+
+fn evaluate(reader: impl Read, part: Part, ctx: &Context) -> Result<String, Box<dyn Error>> {
+    let instructions = instruction_set(part, ctx)?;
+
+    let mut interpreter = Interpreter::new();
+    for scanned in Scanner::new(&instructions, reader) {
+        if let Scanned::Token(Spanned { token, span }) = scanned? {
+            ctx.trace(format_args!("{token} at {span:?}"));
+            interpreter.step(&token);
+        }
+    }
+
+    Ok(interpreter.state.acc.to_string())
+}
+
+pub(crate) fn day_03(input: &str, part: Part, ctx: &Context) -> Result<String, Box<dyn Error>> {
+    match part {
+        Part::Extra("trace") => trace(input, ctx),
+        _ => evaluate(input.as_bytes(), part, ctx),
+    }
+}
+
+pub(crate) fn day_03_stream(
+    reader: &mut dyn Read,
+    part: Part,
+    ctx: &Context,
+) -> Result<String, Box<dyn Error>> {
+    match part {
+        // needs the text for line numbers anyway
+        Part::Extra("trace") => {
+            let mut input = String::new();
+            reader.read_to_string(&mut input)?;
+            trace(&input, ctx)
+        }
+        _ => evaluate(reader, part, ctx),
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::common::Params;
 
     const EXAMPLE_INPUT: &str =
         r"xmul(2,4)%&mul[3,7]!@^do_not_mul(5,5)+mul(32,64]then(mul(11,8)mul(8,5))";
@@ -52,19 +539,224 @@ mod tests {
 
     #[test]
     fn example_input() {
-        assert_eq!(&day_03(EXAMPLE_INPUT, Part::PartOne).unwrap(), "161");
-        assert_eq!(&day_03(EXAMPLE_INPUT_2, Part::PartTwo).unwrap(), "48");
+        assert_eq!(
+            &day_03(EXAMPLE_INPUT, Part::PartOne, &Context::default()).unwrap(),
+            "161"
+        );
+        assert_eq!(
+            &day_03(EXAMPLE_INPUT_2, Part::PartTwo, &Context::default()).unwrap(),
+            "48"
+        );
+    }
+
+    fn spanned<'a>(scanner: impl Iterator<Item = io::Result<Scanned<'a>>>) -> Vec<Spanned<'a>> {
+        scanner
+            .filter_map(|scanned| match scanned.unwrap() {
+                Scanned::Token(spanned) => Some(spanned),
+                Scanned::Skipped { .. } => None,
+            })
+            .collect()
+    }
+
+    fn tokens(instructions: &InstructionSet, input: &str) -> Vec<String> {
+        spanned(Scanner::new(instructions, input.as_bytes()))
+            .into_iter()
+            .map(|spanned| spanned.token.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn example_tokens() {
+        let instructions = "mul,do,don't".parse().unwrap();
+        assert_eq!(
+            spanned(Scanner::new(&instructions, EXAMPLE_INPUT_2.as_bytes()))
+                .into_iter()
+                .map(|spanned| (spanned.token.to_string(), spanned.span))
+                .collect::<Vec<_>>(),
+            vec![
+                ("mul(2,4)".into(), 1..9),
+                ("don't()".into(), 20..27),
+                ("mul(5,5)".into(), 28..36),
+                ("mul(11,8)".into(), 48..57),
+                ("do()".into(), 59..63),
+                ("mul(8,5)".into(), 64..72),
+            ]
+        );
+    }
+
+    #[test]
+    fn operand_digits() {
+        let instructions = "mul,do,don't".parse().unwrap();
+        assert_eq!(
+            tokens(&instructions, "mul(1,999)mul(0,0)"),
+            vec!["mul(1,999)", "mul(0,0)"]
+        );
+        assert!(tokens(&instructions, "mul(1234,5)mul(5,1000)mul(,5)mul(5,)").is_empty());
+        assert!(tokens(&instructions, "mul ( 2 , 4 )mul(2,4").is_empty());
+        assert_eq!(
+            tokens(&instructions, "mmul(2,4)don't()do()"),
+            vec!["mul(2,4)", "don't()", "do()"]
+        );
+    }
+
+    #[test]
+    fn extended_instructions() {
+        let ctx = Context::with_params(Params::from_iter([(
+            "instructions",
+            "mul,add,sub,reset,enable,disable",
+        )]));
+        assert_eq!(
+            &day_03(
+                "mul(2,3)add(1,2)reset()sub(1,5)disable()mul(9,9)do()enable()add(10,10)",
+                Part::PartOne,
+                &ctx
+            )
+            .unwrap(),
+            "16"
+        );
+
+        assert!(day_03(
+            "",
+            Part::PartOne,
+            &Context::with_params(Params::from_iter([("instructions", "mul,div")]))
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn custom_instruction() {
+        let instructions = InstructionSet::new(vec![
+            KNOWN_INSTRUCTIONS[0],
+            Instruction {
+                name: "mad",
+                arity: 3,
+                effect: |state, args| state.acc += (args[0] * args[1] + args[2]) as i64,
+            },
+        ]);
+        assert_eq!(
+            tokens(&instructions, "mad(2,3,4)mad(2,3)mul(2,3)"),
+            vec!["mad(2,3,4)", "mul(2,3)"]
+        );
+
+        let mut interpreter = Interpreter::new();
+        for spanned in spanned(Scanner::new(&instructions, "mad(2,3,4)mul(2,3)".as_bytes())) {
+            interpreter.step(&spanned.token);
+        }
+        assert_eq!(interpreter.state.acc, 16);
+    }
+
+    #[test]
+    fn example_input_trace() {
+        let input = EXAMPLE_INPUT_2.replace("!^", "!\n^");
+        assert_eq!(
+            day_03(&input, Part::Extra("trace"), &Context::default())
+                .unwrap()
+                .lines()
+                .collect::<Vec<_>>(),
+            vec![
+                "@1 1:2 mul(2,4) enabled, total 8",
+                "@10 1:11 skipped \"mul[\" (not a valid mul)",
+                "@21 2:2 don't() enabled, total 8",
+                "@29 2:10 mul(5,5) disabled, total 8",
+                "@38 2:19 skipped \"mul(32,64]\" (not a valid mul)",
+                "@49 2:30 mul(11,8) disabled, total 8",
+                "@60 2:41 do() disabled, total 8",
+                "@65 2:46 mul(8,5) enabled, total 48",
+                "total 48",
+            ]
+        );
+    }
+
+    /// Hands out the input `chunk` bytes at a time, to put chunk boundaries
+    /// everywhere.
+    struct Trickle<'a> {
+        input: &'a [u8],
+        chunk: usize,
+    }
+
+    impl Read for Trickle<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let n = self.chunk.min(buf.len()).min(self.input.len());
+            buf[..n].copy_from_slice(&self.input[..n]);
+            self.input = &self.input[n..];
+            Ok(n)
+        }
+    }
+
+    #[test]
+    fn chunked_input() {
+        let input =
+            format!("{EXAMPLE_INPUT_2}mul(1,1000)mul(123,45)don't()mul(9,9)do()mul(7,").repeat(3);
+        let instructions = "mul,do,don't".parse().unwrap();
+        let describe = |scanned: io::Result<Scanned>| match scanned.unwrap() {
+            Scanned::Token(spanned) => (spanned.token.to_string(), spanned.span),
+            Scanned::Skipped { name, span } => (format!("skipped {name}"), span),
+        };
+        let expected = Scanner::new(&instructions, input.as_bytes())
+            .map(describe)
+            .collect::<Vec<_>>();
+
+        for chunk in 1..=input.len() {
+            let tokens = Scanner::new(
+                &instructions,
+                Trickle {
+                    input: input.as_bytes(),
+                    chunk,
+                },
+            )
+            .map(describe)
+            .collect::<Vec<_>>();
+            assert_eq!(tokens, expected, "chunk size {chunk}");
+
+            for part in [Part::PartOne, Part::PartTwo] {
+                let mut reader = Trickle {
+                    input: input.as_bytes(),
+                    chunk,
+                };
+                assert_eq!(
+                    day_03_stream(&mut reader, part, &Context::default()).unwrap(),
+                    day_03(&input, part, &Context::default()).unwrap(),
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn scanner_memory_is_bounded() {
+        // lots of almost-instructions, and one right at the end
+        let input = "mul(1,2".repeat(100_000) + "mul(3,4)";
+        let instructions = "mul".parse().unwrap();
+        let mut scanner = Scanner::new(&instructions, input.as_bytes());
+        let token = scanner
+            .find_map(|scanned| match scanned.unwrap() {
+                Scanned::Token(spanned) => Some(spanned.token.to_string()),
+                Scanned::Skipped { .. } => None,
+            })
+            .unwrap();
+        assert_eq!(token, "mul(3,4)");
+        assert!(scanner.buf.len() <= CHUNK_SIZE + 16);
+        assert!(scanner.next().is_none());
     }
 
     #[cfg(feature = "regression")]
     #[test]
     fn regression() {
         assert_eq!(
-            &day_03(include_str!("../inputs/03.txt"), Part::PartOne).unwrap(),
+            &day_03(
+                include_str!("../inputs/03.txt"),
+                Part::PartOne,
+                &Context::default()
+            )
+            .unwrap(),
             "183788984"
         );
         assert_eq!(
-            &day_03(include_str!("../inputs/03.txt"), Part::PartTwo).unwrap(),
+            &day_03(
+                include_str!("../inputs/03.txt"),
+                Part::PartTwo,
+                &Context::default()
+            )
+            .unwrap(),
             "62098619"
         );
     }
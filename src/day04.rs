@@ -1,83 +1,645 @@
-use std::error::Error;
+use std::{
+    collections::{HashMap, HashSet},
+    error::Error,
+    fmt::Display,
+    fs,
+    str::FromStr,
+};
 
 use itertools::Itertools;
+use thiserror::Error;
 
-use crate::common::Part;
+use crate::common::{Context, ParamError, ParamSpec, Part, PartSpec};
 
-fn check_part1<'a, I: IntoIterator<Item = &'a char>>(slice: I) -> bool {
-    let tuple: (char, char, char, char) =
-        slice.into_iter().copied().take(4).collect_tuple().unwrap();
+const WORD: ParamSpec = ParamSpec {
+    name: "word",
+    default: "XMAS",
+    help: "word to search for in part one",
+    file: false,
+};
 
-    tuple == ('X', 'M', 'A', 'S') || tuple == ('S', 'A', 'M', 'X')
+const SYMMETRY: ParamSpec = ParamSpec {
+    name: "symmetry",
+    default: "all",
+    help: "orientations to match patterns in: all (rotations and reflections), rotations or none",
+    file: false,
+};
+
+const DICTIONARY: ParamSpec = ParamSpec {
+    name: "dictionary",
+    default: "",
+    help: "file with one word per line for the words part; just the word param if empty",
+    file: true,
+};
+
+const COLOUR: ParamSpec = ParamSpec {
+    name: "colour",
+    default: "false",
+    help: "in the render part, highlight matched letters with ANSI colours instead of blanking out the rest",
+    file: false,
+};
+
+const WRAP: ParamSpec = ParamSpec {
+    name: "wrap",
+    default: "false",
+    help: "let rows, columns and diagonals continue across the grid's edges",
+    file: false,
+};
+
+pub(crate) const PARAMS: &[ParamSpec] = &[WORD, SYMMETRY, DICTIONARY, COLOUR, WRAP];
+
+pub(crate) const EXTRA_PARTS: &[PartSpec] = &[
+    PartSpec {
+        name: "words",
+        help: "find every dictionary word in all eight directions, with where it starts and which way it goes",
+    },
+    PartSpec {
+        name: "render",
+        help: "reprint the grid with only the letters of dictionary words kept, and count them per direction",
+    },
+    PartSpec {
+        name: "render-x-mas",
+        help: "reprint the grid with only the letters of part two's X-MASes kept, and count them per orientation",
+    },
+];
+
+/// The X-MAS from part two.
+const X_MAS: &str = "M.S/.A./M.S";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Symmetry {
+    /// All eight: four rotations, each optionally mirrored.
+    All,
+    Rotations,
+    None,
 }
 
-pub(crate) fn day_04(input: &str, part: Part) -> Result<String, Box<dyn Error>> {
-    let grid = input
-        .lines()
-        .map(|line| line.chars().collect::<Vec<_>>())
-        .collect::<Vec<_>>();
+impl FromStr for Symmetry {
+    type Err = String;
 
-    let mut count = 0;
-    match part {
-        Part::PartOne => {
-            for (y, x) in (0..grid.len()).cartesian_product(0..grid[0].len()) {
-                // forwards and backwards
-                if x <= grid[y].len() - 4 && check_part1(&grid[y][x..x + 4]) {
-                    count += 1;
-                }
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "all" => Ok(Self::All),
+            "rotations" => Ok(Self::Rotations),
+            "none" => Ok(Self::None),
+            _ => Err(format!("expected all, rotations or none, got {s}")),
+        }
+    }
+}
 
-                // up and down
-                if y <= grid.len() - 4 && check_part1(grid[y..y + 4].iter().map(|row| &row[x])) {
-                    count += 1
-                }
+#[derive(Error, Debug)]
+enum GridError {
+    #[error("line {line} has {len} letters, but the first has {width}")]
+    Ragged {
+        line: usize,
+        len: usize,
+        width: usize,
+    },
+}
 
-                // forward diagonal
-                if x <= grid.len() - 4
-                    && y <= grid.len() - 4
-                    && check_part1(
-                        grid[y..y + 4]
-                            .iter()
-                            .enumerate()
-                            .map(|(offset, row)| &row[x + offset]),
-                    )
-                {
-                    count += 1;
-                }
+/// The word search itself; every row has the same width.
+#[derive(Debug)]
+struct Grid {
+    rows: Vec<Vec<char>>,
+    width: usize,
+    /// Whether it's really a torus, with each edge joined to the opposite one.
+    wrap: bool,
+}
+
+impl FromStr for Grid {
+    type Err = GridError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let rows = s
+            .lines()
+            .map(|line| line.chars().collect::<Vec<_>>())
+            .collect::<Vec<_>>();
+        let width = rows.first().map_or(0, Vec::len);
+        if let Some((i, row)) = rows.iter().enumerate().find(|(_, row)| row.len() != width) {
+            return Err(GridError::Ragged {
+                line: i + 1,
+                len: row.len(),
+                width,
+            });
+        }
+
+        Ok(Self {
+            rows,
+            width,
+            wrap: false,
+        })
+    }
+}
+
+impl Grid {
+    fn height(&self) -> usize {
+        self.rows.len()
+    }
+
+    /// The letter at (`x`, `y`), which may be off the bottom or right edge
+    /// when wrapping.
+    fn at(&self, x: usize, y: usize) -> char {
+        self.rows[y % self.height()][x % self.width]
+    }
+
+    /// The next cell from (`x`, `y`) in `direction`, if it's still in the grid.
+    fn step(&self, (x, y): (usize, usize), direction: Direction) -> Option<(usize, usize)> {
+        let (dx, dy) = direction.as_vec();
+        if self.wrap {
+            return Some((
+                (x as isize + dx).rem_euclid(self.width as isize) as usize,
+                (y as isize + dy).rem_euclid(self.height() as isize) as usize,
+            ));
+        }
+
+        match (x.checked_add_signed(dx), y.checked_add_signed(dy)) {
+            (Some(nx), Some(ny)) if nx < self.width && ny < self.height() => Some((nx, ny)),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Error, Debug)]
+enum PatternError {
+    #[error("empty pattern")]
+    Empty,
+    #[error("pattern rows have different lengths")]
+    Ragged,
+}
+
+/// The cells of one match of a pattern, sorted.
+type Footprint = Vec<(usize, usize)>;
+
+/// A small 2D template to look for in the grid. `None` cells match anything.
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Pattern {
+    rows: Vec<Vec<Option<char>>>,
+}
+
+/// Rows separated by `/`, with `.` as the wildcard.
+impl FromStr for Pattern {
+    type Err = PatternError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let rows = s
+            .split('/')
+            .map(|row| {
+                row.chars()
+                    .map(|c| (c != '.').then_some(c))
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+        if rows[0].is_empty() {
+            return Err(PatternError::Empty);
+        }
+        if rows.iter().any(|row| row.len() != rows[0].len()) {
+            return Err(PatternError::Ragged);
+        }
+
+        Ok(Self { rows })
+    }
+}
+
+/// The same `M.S/.A./M.S` form it's parsed from.
+impl Display for Pattern {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let rows = self.rows.iter().map(|row| {
+            row.iter()
+                .map(|cell| cell.unwrap_or('.'))
+                .collect::<String>()
+        });
+        write!(f, "{}", rows.format("/"))
+    }
+}
+
+impl Pattern {
+    /// `word` written left to right.
+    fn horizontal(word: &[char]) -> Self {
+        Self {
+            rows: vec![word.iter().copied().map(Some).collect()],
+        }
+    }
+
+    /// `word` written from the top left corner down to the bottom right.
+    fn diagonal(word: &[char]) -> Self {
+        let rows = (0..word.len())
+            .map(|y| {
+                (0..word.len())
+                    .map(|x| (x == y).then_some(word[y]))
+                    .collect()
+            })
+            .collect();
+        Self { rows }
+    }
+
+    fn height(&self) -> usize {
+        self.rows.len()
+    }
+
+    fn width(&self) -> usize {
+        self.rows[0].len()
+    }
+
+    /// A quarter turn clockwise.
+    fn rotated(&self) -> Self {
+        let rows = (0..self.width())
+            .map(|x| (0..self.height()).rev().map(|y| self.rows[y][x]).collect())
+            .collect();
+        Self { rows }
+    }
+
+    /// Mirrored left to right.
+    fn reflected(&self) -> Self {
+        let rows = self
+            .rows
+            .iter()
+            .map(|row| row.iter().rev().copied().collect())
+            .collect();
+        Self { rows }
+    }
+
+    /// Every distinct orientation of the pattern under `symmetry`. Symmetric
+    /// patterns have fewer, so e.g. a palindrome isn't counted twice.
+    fn orientations(&self, symmetry: Symmetry) -> Vec<Pattern> {
+        let mut candidates = vec![self.clone()];
+        if symmetry != Symmetry::None {
+            for _ in 0..3 {
+                let next = candidates.last().unwrap().rotated();
+                candidates.push(next);
+            }
+        }
+        if symmetry == Symmetry::All {
+            let reflections = candidates
+                .iter()
+                .map(Pattern::reflected)
+                .collect::<Vec<_>>();
+            candidates.extend(reflections);
+        }
+
+        let mut orientations = Vec::new();
+        for candidate in candidates {
+            if !orientations.contains(&candidate) {
+                orientations.push(candidate);
+            }
+        }
+        orientations
+    }
+
+    /// Whether the pattern matches with its top left corner at (`x`, `y`),
+    /// which has to leave room for the whole pattern unless the grid wraps.
+    fn matches_at(&self, grid: &Grid, x: usize, y: usize) -> bool {
+        self.rows.iter().enumerate().all(|(dy, row)| {
+            row.iter().enumerate().all(|(dx, cell)| match cell {
+                Some(c) => grid.at(x + dx, y + dy) == *c,
+                None => true,
+            })
+        })
+    }
+
+    /// The cells (sorted) covered by the non-wildcard parts of each match.
+    fn footprints(&self, grid: &Grid) -> Vec<Footprint> {
+        // without wrapping, no room at all if the pattern is bigger than the
+        // grid either way
+        let (Some(max_x), Some(max_y)) = (if grid.wrap {
+            (Some(grid.width), Some(grid.height()))
+        } else {
+            (
+                (grid.width + 1).checked_sub(self.width()),
+                (grid.height() + 1).checked_sub(self.height()),
+            )
+        }) else {
+            return Vec::new();
+        };
 
-                // reverse diagonal
-                if x >= 3
-                    && y <= grid.len() - 4
-                    && check_part1(
-                        grid[y..y + 4]
-                            .iter()
-                            .enumerate()
-                            .map(|(offset, row)| &row[x - offset]),
-                    )
-                {
-                    count += 1;
+        (0..max_y)
+            .flat_map(|y| (0..max_x).map(move |x| (x, y)))
+            .filter(|&(x, y)| self.matches_at(grid, x, y))
+            .map(|(x, y)| {
+                let mut cells = Vec::new();
+                for (dy, row) in self.rows.iter().enumerate() {
+                    for (dx, cell) in row.iter().enumerate() {
+                        if cell.is_some() {
+                            cells.push(((x + dx) % grid.width, (y + dy) % grid.height()));
+                        }
+                    }
                 }
+                cells.sort_unstable();
+                cells
+            })
+            .collect()
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+enum Direction {
+    N,
+    NE,
+    E,
+    SE,
+    S,
+    SW,
+    W,
+    NW,
+}
+
+impl Direction {
+    const ALL: [Direction; 8] = [
+        Direction::N,
+        Direction::NE,
+        Direction::E,
+        Direction::SE,
+        Direction::S,
+        Direction::SW,
+        Direction::W,
+        Direction::NW,
+    ];
+
+    /// With y going down the grid.
+    fn as_vec(&self) -> (isize, isize) {
+        match self {
+            Direction::N => (0, -1),
+            Direction::NE => (1, -1),
+            Direction::E => (1, 0),
+            Direction::SE => (1, 1),
+            Direction::S => (0, 1),
+            Direction::SW => (-1, 1),
+            Direction::W => (-1, 0),
+            Direction::NW => (-1, -1),
+        }
+    }
+}
+
+impl Display for Direction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{self:?}")
+    }
+}
+
+#[derive(Debug, Default)]
+struct TrieNode {
+    children: HashMap<char, usize>,
+    /// Index into the dictionary of the word ending here, if any.
+    word: Option<usize>,
+}
+
+/// All the dictionary's words in one prefix tree, so each walk through the
+/// grid checks every word at once instead of rescanning per word.
+#[derive(Debug)]
+struct Trie {
+    nodes: Vec<TrieNode>,
+}
+
+impl Trie {
+    fn new<S: AsRef<str>>(words: &[S]) -> Self {
+        let mut nodes = vec![TrieNode::default()];
+        for (i, word) in words.iter().enumerate() {
+            let mut node = 0;
+            for c in word.as_ref().chars() {
+                node = match nodes[node].children.get(&c) {
+                    Some(&child) => child,
+                    None => {
+                        nodes.push(TrieNode::default());
+                        let child = nodes.len() - 1;
+                        nodes[node].children.insert(c, child);
+                        child
+                    }
+                };
             }
+            nodes[node].word = Some(i);
         }
-        Part::PartTwo => {
-            for (y, x) in (1..grid.len() - 1).cartesian_product(1..grid[0].len() - 1) {
-                if grid[y][x] == 'A'
-                    && ((grid[y - 1][x - 1] == 'S' && grid[y + 1][x + 1] == 'M')
-                        || (grid[y - 1][x - 1] == 'M' && grid[y + 1][x + 1] == 'S'))
-                    && ((grid[y - 1][x + 1] == 'S' && grid[y + 1][x - 1] == 'M')
-                        || (grid[y - 1][x + 1] == 'M' && grid[y + 1][x - 1] == 'S'))
-                {
-                    count += 1;
+
+        Self { nodes }
+    }
+}
+
+/// A dictionary word found in the grid.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Hit {
+    /// Index into the dictionary.
+    word: usize,
+    /// (x, y) of the first letter.
+    start: (usize, usize),
+    direction: Direction,
+}
+
+/// Every dictionary word in the grid, in all eight directions, ordered by
+/// start position and then direction. A word that covers the same cells more
+/// than once - a single letter in every direction, a palindrome read both
+/// ways, or going either way round a wrapping grid that's too narrow - is only
+/// a hit the first time, the same as a `Footprint`.
+fn find_words(grid: &Grid, trie: &Trie) -> Vec<Hit> {
+    let mut hits = Vec::new();
+    let mut seen = HashSet::new();
+    for (y, x) in (0..grid.height()).cartesian_product(0..grid.width) {
+        for direction in Direction::ALL {
+            let (mut cx, mut cy) = (x, y);
+            let mut node = 0;
+            let mut path = Vec::new();
+            while let Some(&child) = trie.nodes[node].children.get(&grid.rows[cy][cx]) {
+                node = child;
+                path.push((cx, cy));
+                if let Some(word) = trie.nodes[node].word {
+                    let mut footprint: Footprint = path.clone();
+                    footprint.sort_unstable();
+                    if seen.insert((word, footprint)) {
+                        hits.push(Hit {
+                            word,
+                            start: (x, y),
+                            direction,
+                        });
+                    }
+                }
+
+                match grid.step((cx, cy), direction) {
+                    Some(next) => (cx, cy) = next,
+                    None => break,
                 }
             }
         }
     }
 
+    hits
+}
+
+/// Reprints `grid` keeping only the letters in `cells`, like the puzzle's
+/// illustration, followed by a count per label and the total. With `colour`,
+/// all letters are kept and those in `cells` are highlighted instead.
+fn render(
+    grid: &Grid,
+    cells: impl IntoIterator<Item = (usize, usize)>,
+    counts: &[(String, usize)],
+    colour: bool,
+) -> String {
+    let mut matched = vec![vec![false; grid.width]; grid.height()];
+    for (x, y) in cells {
+        matched[y][x] = true;
+    }
+
+    let mut lines = grid
+        .rows
+        .iter()
+        .zip(&matched)
+        .map(|(row, matched)| {
+            row.iter()
+                .zip(matched)
+                .map(|(&c, &matched)| match (matched, colour) {
+                    (true, false) => c.to_string(),
+                    (true, true) => format!("\x1b[1;31m{c}\x1b[0m"),
+                    (false, false) => ".".into(),
+                    (false, true) => format!("\x1b[2m{c}\x1b[0m"),
+                })
+                .collect::<String>()
+        })
+        .collect::<Vec<_>>();
+
+    lines.push(String::new());
+    for (label, count) in counts {
+        lines.push(format!("{label}: {count}"));
+    }
+    let total = counts.iter().map(|(_, count)| count).sum::<usize>();
+    lines.push(format!("total: {total}"));
+
+    lines.join("\n")
+}
+
+/// The cells each hit covers.
+fn hit_cells<'a, S: AsRef<str>>(
+    grid: &'a Grid,
+    words: &'a [S],
+    hits: &'a [Hit],
+) -> impl Iterator<Item = (usize, usize)> + 'a {
+    hits.iter().flat_map(|hit| {
+        let len = words[hit.word].as_ref().chars().count();
+        // only None at the end of a hit that finishes on the edge
+        std::iter::successors(Some(hit.start), |&cell| grid.step(cell, hit.direction)).take(len)
+    })
+}
+
+/// Every distinct match of `patterns` in any orientation, grouped by the
+/// orientation that found it first.
+fn pattern_matches(
+    grid: &Grid,
+    patterns: &[Pattern],
+    symmetry: Symmetry,
+) -> Vec<(Pattern, Vec<Footprint>)> {
+    // different orientations can only match the same cells when wrapping (or
+    // for single letters), but then they'd be counted twice
+    let mut seen = HashSet::new();
+    patterns
+        .iter()
+        .flat_map(|pattern| pattern.orientations(symmetry))
+        .map(|orientation| {
+            let footprints = orientation
+                .footprints(grid)
+                .into_iter()
+                .filter(|footprint| seen.insert(footprint.clone()))
+                .collect();
+            (orientation, footprints)
+        })
+        .collect()
+}
+
+/// The `word` param, which would match everywhere (or nowhere) if empty.
+fn word(ctx: &Context) -> Result<String, ParamError> {
+    let word = ctx.params.get::<String>(&WORD)?;
+    if word.is_empty() {
+        return Err(ParamError::Invalid {
+            name: WORD.name,
+            value: word,
+            reason: "can't search for an empty word".to_string(),
+        });
+    }
+    Ok(word)
+}
+
+fn dictionary(ctx: &Context) -> Result<Vec<String>, Box<dyn Error>> {
+    let path = ctx.params.get::<String>(&DICTIONARY)?;
+    if path.is_empty() {
+        return Ok(vec![word(ctx)?]);
+    }
+
+    let words =
+        fs::read_to_string(&path).map_err(|e| format!("can't read dictionary {path}: {e}"))?;
+    Ok(words
+        .lines()
+        .map(str::trim)
+        .filter(|word| !word.is_empty())
+        .map(String::from)
+        .collect())
+}
+
+pub(crate) fn day_04(input: &str, part: Part, ctx: &Context) -> Result<String, Box<dyn Error>> {
+    let symmetry = ctx.params.get::<Symmetry>(&SYMMETRY)?;
+
+    let mut grid = input.parse::<Grid>()?;
+    grid.wrap = ctx.params.get(&WRAP)?;
+
+    if part == Part::Extra("render-x-mas") {
+        let matches = pattern_matches(&grid, &[X_MAS.parse()?], symmetry);
+        let counts = matches
+            .iter()
+            .map(|(orientation, footprints)| (orientation.to_string(), footprints.len()))
+            .collect::<Vec<_>>();
+        let cells = matches
+            .into_iter()
+            .flat_map(|(_, footprints)| footprints)
+            .flatten();
+        return Ok(render(&grid, cells, &counts, ctx.params.get(&COLOUR)?));
+    }
+
+    if let Part::Extra(extra) = part {
+        let words = dictionary(ctx)?;
+        let hits = find_words(&grid, &Trie::new(&words));
+        ctx.debug(format_args!(
+            "{} hits for {} words",
+            hits.len(),
+            words.len()
+        ));
+        return match extra {
+            "words" => Ok(hits
+                .iter()
+                .map(|hit| {
+                    let (x, y) = hit.start;
+                    format!("{} at ({x}, {y}) going {}", words[hit.word], hit.direction)
+                })
+                .join("\n")),
+            "render" => {
+                let counts = Direction::ALL
+                    .iter()
+                    .map(|&direction| {
+                        let count = hits.iter().filter(|hit| hit.direction == direction).count();
+                        (direction.to_string(), count)
+                    })
+                    .collect::<Vec<_>>();
+                let cells = hit_cells(&grid, &words, &hits);
+                Ok(render(&grid, cells, &counts, ctx.params.get(&COLOUR)?))
+            }
+            _ => Err(part.unsupported()),
+        };
+    }
+
+    let patterns = match part {
+        Part::PartOne => {
+            let word = word(ctx)?.chars().collect::<Vec<_>>();
+            vec![Pattern::horizontal(&word), Pattern::diagonal(&word)]
+        }
+        Part::PartTwo => vec![X_MAS.parse()?],
+        Part::Extra(_) => return Err(part.unsupported()),
+    };
+
+    let matches = pattern_matches(&grid, &patterns, symmetry);
+    let count = matches
+        .iter()
+        .map(|(_, footprints)| footprints.len())
+        .sum::<usize>();
     Ok(count.to_string())
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::common::Params;
+    use crate::runner::normalise;
 
     const EXAMPLE_INPUT: &str = r"MMMSXXMASM
 MSAMXMSMSA
@@ -92,19 +654,340 @@ MXMXAXMASX";
 
     #[test]
     fn example_input() {
-        assert_eq!(&day_04(EXAMPLE_INPUT, Part::PartOne).unwrap(), "18");
-        assert_eq!(&day_04(EXAMPLE_INPUT, Part::PartTwo).unwrap(), "9");
+        assert_eq!(
+            &day_04(EXAMPLE_INPUT, Part::PartOne, &Context::default()).unwrap(),
+            "18"
+        );
+        assert_eq!(
+            &day_04(EXAMPLE_INPUT, Part::PartTwo, &Context::default()).unwrap(),
+            "9"
+        );
+    }
+
+    #[test]
+    fn example_input_params() {
+        let ctx = Context::with_params(Params::from_iter([("word", "MAS")]));
+        assert_eq!(&day_04(EXAMPLE_INPUT, Part::PartOne, &ctx).unwrap(), "38");
+
+        let ctx = Context::with_params(Params::from_iter([("word", "")]));
+        for part in [Part::PartOne, Part::Extra("words"), Part::Extra("render")] {
+            assert!(day_04(EXAMPLE_INPUT, part, &ctx)
+                .unwrap_err()
+                .to_string()
+                .contains("empty word"));
+        }
+    }
+
+    #[test]
+    fn example_input_trailing_blank_lines() {
+        let input = format!("{EXAMPLE_INPUT}\n\n\n");
+        let (input, _) = normalise(&input);
+        assert_eq!(
+            &day_04(&input, Part::PartOne, &Context::default()).unwrap(),
+            "18"
+        );
+        assert_eq!(
+            &day_04(&input, Part::PartTwo, &Context::default()).unwrap(),
+            "9"
+        );
+    }
+
+    #[test]
+    fn pattern_orientations() {
+        let xmas = Pattern::horizontal(&['X', 'M', 'A', 'S']);
+        assert_eq!(xmas.orientations(Symmetry::All).len(), 4);
+        assert_eq!(xmas.orientations(Symmetry::Rotations).len(), 4);
+        assert_eq!(xmas.orientations(Symmetry::None), vec![xmas.clone()]);
+        assert_eq!(
+            xmas.rotated().rows,
+            "X/M/A/S".parse::<Pattern>().unwrap().rows
+        );
+
+        let x_mas = X_MAS.parse::<Pattern>().unwrap();
+        assert_eq!(x_mas.orientations(Symmetry::All).len(), 4);
+        assert_eq!(x_mas.rotated(), "M.M/.A./S.S".parse::<Pattern>().unwrap());
+
+        // the L tetromino isn't symmetric at all
+        let l = "X./X./XX".parse::<Pattern>().unwrap();
+        assert_eq!(l.orientations(Symmetry::All).len(), 8);
+        assert_eq!(l.orientations(Symmetry::Rotations).len(), 4);
+
+        assert!(matches!(
+            "AB/C".parse::<Pattern>(),
+            Err(PatternError::Ragged)
+        ));
+        assert!(matches!("".parse::<Pattern>(), Err(PatternError::Empty)));
+    }
+
+    #[test]
+    fn example_input_symmetry() {
+        // only left to right and top left to bottom right
+        let ctx = Context::with_params(Params::from_iter([("symmetry", "none")]));
+        assert_eq!(&day_04(EXAMPLE_INPUT, Part::PartOne, &ctx).unwrap(), "4");
+        assert_eq!(&day_04(EXAMPLE_INPUT, Part::PartTwo, &ctx).unwrap(), "2");
+
+        let ctx = Context::with_params(Params::from_iter([("symmetry", "rotations")]));
+        assert_eq!(&day_04(EXAMPLE_INPUT, Part::PartOne, &ctx).unwrap(), "18");
+        assert_eq!(&day_04(EXAMPLE_INPUT, Part::PartTwo, &ctx).unwrap(), "9");
+    }
+
+    #[test]
+    fn tall_grid() {
+        let input = "XS\nMA\nAM\nSX\n..\n..";
+        assert_eq!(
+            &day_04(input, Part::PartOne, &Context::default()).unwrap(),
+            "2"
+        );
+        let ctx = Context::with_params(Params::from_iter([("word", "MAS")]));
+        assert_eq!(&day_04(input, Part::PartOne, &ctx).unwrap(), "2");
+        assert_eq!(
+            &day_04(input, Part::PartTwo, &Context::default()).unwrap(),
+            "0"
+        );
+    }
+
+    #[test]
+    fn wide_grid() {
+        let input = "XMASAMXMAS\nMMSAMXMASS";
+        assert_eq!(
+            &day_04(input, Part::PartOne, &Context::default()).unwrap(),
+            "5"
+        );
+        let ctx = Context::with_params(Params::from_iter([("word", "MAS")]));
+        assert_eq!(&day_04(input, Part::PartOne, &ctx).unwrap(), "5");
+        assert_eq!(
+            &day_04(
+                "M.S.M.S\n.A...A.\nM.S.M.S",
+                Part::PartTwo,
+                &Context::default()
+            )
+            .unwrap(),
+            "2"
+        );
+    }
+
+    #[test]
+    fn tiny_grid() {
+        for input in ["", "X", "XM\nAS", "XMA\nMAS\nSAX"] {
+            assert_eq!(
+                &day_04(input, Part::PartOne, &Context::default()).unwrap(),
+                "0",
+                "{input:?}"
+            );
+            assert_eq!(
+                &day_04(input, Part::PartTwo, &Context::default()).unwrap(),
+                "0",
+                "{input:?}"
+            );
+        }
+        let ctx = Context::with_params(Params::from_iter([("word", "X")]));
+        assert_eq!(&day_04("X", Part::PartOne, &ctx).unwrap(), "1");
+    }
+
+    #[test]
+    fn ragged_grid() {
+        let err = day_04("XMAS\nXM\nXMAS", Part::PartOne, &Context::default()).unwrap_err();
+        assert_eq!(err.to_string(), "line 2 has 2 letters, but the first has 4");
+    }
+
+    #[test]
+    fn find_words_trie() {
+        let grid = EXAMPLE_INPUT.parse::<Grid>().unwrap();
+        let hits = find_words(&grid, &Trie::new(&["XMAS"]));
+        assert_eq!(hits.len(), 18);
+        assert_eq!(
+            hits[0],
+            Hit {
+                word: 0,
+                start: (4, 0),
+                direction: Direction::SE
+            }
+        );
+
+        // shared prefixes, and words inside other words
+        let words = ["XMAS", "MAS", "XM", "SAMX", "A"];
+        let hits = find_words(&grid, &Trie::new(&words));
+        for (i, word) in words.iter().enumerate() {
+            let chars = word.chars().collect::<Vec<_>>();
+            let expected = if chars.len() == 1 {
+                Pattern::horizontal(&chars).footprints(&grid).len()
+            } else {
+                Pattern::horizontal(&chars)
+                    .orientations(Symmetry::All)
+                    .iter()
+                    .chain(&Pattern::diagonal(&chars).orientations(Symmetry::All))
+                    .map(|pattern| pattern.footprints(&grid).len())
+                    .sum()
+            };
+            assert_eq!(
+                hits.iter().filter(|hit| hit.word == i).count(),
+                expected,
+                "{word}"
+            );
+        }
+    }
+
+    #[test]
+    fn example_input_words() {
+        let input = "XMAS\n.A..\n..S.";
+        let ctx = Context::with_params(Params::from_iter([("word", "SAX")]));
+        assert_eq!(
+            day_04(input, Part::Extra("words"), &ctx).unwrap(),
+            "SAX at (2, 2) going NW"
+        );
+
+        let path = std::env::temp_dir().join(format!("aoc2024-day04-{}.txt", std::process::id()));
+        fs::write(&path, "XMAS\nMA\n\nSAM\n").unwrap();
+        let ctx = Context::with_params(Params::from_iter([("dictionary", path.to_str().unwrap())]));
+        let result = day_04(input, Part::Extra("words"), &ctx);
+        fs::remove_file(&path).unwrap();
+        assert_eq!(
+            result.unwrap().lines().collect::<Vec<_>>(),
+            vec![
+                "XMAS at (0, 0) going E",
+                "MA at (1, 0) going E",
+                "MA at (1, 0) going S",
+                "SAM at (3, 0) going W",
+            ]
+        );
+    }
+
+    #[test]
+    fn example_input_render() {
+        let rendered = day_04(EXAMPLE_INPUT, Part::Extra("render"), &Context::default()).unwrap();
+        assert_eq!(
+            rendered.lines().collect::<Vec<_>>(),
+            vec![
+                "....XXMAS.",
+                ".SAMXMS...",
+                "...S..A...",
+                "..A.A.MS.X",
+                "XMASAMX.MM",
+                "X.....XA.A",
+                "S.S.S.S.SS",
+                ".A.A.A.A.A",
+                "..M.M.M.MM",
+                ".X.X.XMASX",
+                "",
+                "N: 2",
+                "NE: 4",
+                "E: 3",
+                "SE: 1",
+                "S: 1",
+                "SW: 1",
+                "W: 2",
+                "NW: 4",
+                "total: 18",
+            ]
+        );
+
+        let ctx = Context::with_params(Params::from_iter([("colour", "true")]));
+        let rendered = day_04("XMAS\nAB..", Part::Extra("render"), &ctx).unwrap();
+        assert_eq!(
+            rendered.lines().take(2).collect::<Vec<_>>(),
+            vec![
+                "\x1b[1;31mX\x1b[0m\x1b[1;31mM\x1b[0m\x1b[1;31mA\x1b[0m\x1b[1;31mS\x1b[0m",
+                "\x1b[2mA\x1b[0m\x1b[2mB\x1b[0m\x1b[2m.\x1b[0m\x1b[2m.\x1b[0m",
+            ]
+        );
+    }
+
+    #[test]
+    fn example_input_render_x_mas() {
+        let rendered = day_04(
+            EXAMPLE_INPUT,
+            Part::Extra("render-x-mas"),
+            &Context::default(),
+        )
+        .unwrap();
+        assert_eq!(
+            rendered.lines().collect::<Vec<_>>(),
+            vec![
+                ".M.S......",
+                "..A..MSMS.",
+                ".M.S.MAA..",
+                "..A.ASMSM.",
+                ".M.S.M....",
+                "..........",
+                "S.S.S.S.S.",
+                ".A.A.A.A..",
+                "M.M.M.M.M.",
+                "..........",
+                "",
+                "M.S/.A./M.S: 2",
+                "M.M/.A./S.S: 1",
+                "S.M/.A./S.M: 1",
+                "S.S/.A./M.M: 5",
+                "total: 9",
+            ]
+        );
+    }
+
+    #[test]
+    fn example_input_wrap() {
+        let ctx = Context::with_params(Params::from_iter([("wrap", "true")]));
+        assert_eq!(&day_04(EXAMPLE_INPUT, Part::PartOne, &ctx).unwrap(), "21");
+        assert_eq!(
+            day_04(EXAMPLE_INPUT, Part::Extra("words"), &ctx)
+                .unwrap()
+                .lines()
+                .count(),
+            21
+        );
+        // centred on the corner
+        let input = "A...\n.S.M\n....\n.S.M";
+        assert_eq!(&day_04(input, Part::PartTwo, &ctx).unwrap(), "1");
+        assert_eq!(
+            &day_04(input, Part::PartTwo, &Context::default()).unwrap(),
+            "0"
+        );
+    }
+
+    #[test]
+    fn wrap_small_grids() {
+        let count = |input: &str, word: &str| {
+            let ctx = Context::with_params(Params::from_iter([("wrap", "true"), ("word", word)]));
+            let part_one = day_04(input, Part::PartOne, &ctx).unwrap();
+            let words = day_04(input, Part::Extra("words"), &ctx).unwrap();
+            assert_eq!(
+                part_one,
+                words.lines().count().to_string(),
+                "{input:?}, {word}"
+            );
+            part_one
+        };
+
+        // the diagonals of a single row are the row itself
+        assert_eq!(count("ASXM", "XMAS"), "1");
+        // going round more than once
+        assert_eq!(count("A", "AAA"), "1");
+        assert_eq!(count("AB", "ABA"), "1");
+        assert_eq!(count("AB\nBA", "ABAB"), "4");
+        assert_eq!(count("AB\nBA", "A"), "2");
+        // palindromes read the same both ways round
+        assert_eq!(count("ABAC", "ABA"), "1");
+        assert_eq!(count("XABAX\nXXXXX", "ABA"), "1");
     }
 
     #[cfg(feature = "regression")]
     #[test]
     fn regression() {
         assert_eq!(
-            &day_04(include_str!("../inputs/04.txt"), Part::PartOne).unwrap(),
+            &day_04(
+                include_str!("../inputs/04.txt"),
+                Part::PartOne,
+                &Context::default()
+            )
+            .unwrap(),
             "2344"
         );
         assert_eq!(
-            &day_04(include_str!("../inputs/04.txt"), Part::PartTwo).unwrap(),
+            &day_04(
+                include_str!("../inputs/04.txt"),
+                Part::PartTwo,
+                &Context::default()
+            )
+            .unwrap(),
             "1815"
         );
     }
@@ -1,21 +1,91 @@
 use std::{
-    collections::{HashMap, HashSet},
+    cmp::Reverse,
+    collections::{BinaryHeap, HashMap, HashSet},
     error::Error,
-    hash::RandomState,
+    fmt::Display,
+    num::ParseIntError,
+    str::FromStr,
 };
 
 use itertools::Itertools;
+use thiserror::Error;
 
-use crate::common::Part;
+use crate::common::{Context, ParamSpec, Part, PartSpec};
 
+const VALIDITY: ParamSpec = ParamSpec {
+    name: "validity",
+    default: "puzzle",
+    help: "when an update is in the right order: puzzle (no two pages break a rule) or strict (no chain of rules between its pages is broken either, and chains of rules put each page before the next)",
+    file: false,
+};
+
+const UPDATE: ParamSpec = ParamSpec {
+    name: "update",
+    default: "0",
+    help: "update to draw in the dot part, counting from 1; 0 draws every rule",
+    file: false,
+};
+
+pub(crate) const PARAMS: &[ParamSpec] = &[VALIDITY, UPDATE];
+
+pub(crate) const EXTRA_PARTS: &[PartSpec] = &[
+    PartSpec {
+        name: "validate",
+        help: "list each update with whether it's in the right order, and which rule it breaks if not",
+    },
+    PartSpec {
+        name: "orderings",
+        help: "count the orders each update's pages could go in without breaking a rule",
+    },
+    PartSpec {
+        name: "repair",
+        help: "find the fewest pages to move to put each update in order, and where they go",
+    },
+    PartSpec {
+        name: "queries",
+        help: "go through the input in order, validating and fixing each update with the rules so far; `!a|b` takes a rule back",
+    },
+    PartSpec {
+        name: "dot",
+        help: "draw the rules as a Graphviz graph, or just those of one update with the ones it breaks in red (and any cycle among them in orange)",
+    },
+];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Validity {
+    Puzzle,
+    Strict,
+}
+
+impl FromStr for Validity {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "puzzle" => Ok(Self::Puzzle),
+            "strict" => Ok(Self::Strict),
+            _ => Err(format!("expected puzzle or strict, got {s}")),
+        }
+    }
+}
+
+fn parse_rule(line: &str) -> Result<Option<(u32, u32)>, ParseIntError> {
+    match line.split_once('|') {
+        Some((former, latter)) => Ok(Some((former.parse()?, latter.parse()?))),
+        None => Ok(None),
+    }
+}
+
+/// The rules as they are at the end of the input: `!a|b` takes back `a|b`.
 fn gen_digraph(input: &str) -> Result<HashMap<u32, HashSet<u32>>, Box<dyn Error>> {
     // page -> set of following pages
     let mut digraph: HashMap<u32, HashSet<u32>> = HashMap::new();
     for line in input.lines() {
-        if let Some((former, latter)) = line.split_once('|') {
-            let former = former.parse()?;
-            let latter = latter.parse()?;
-
+        if let Some(line) = line.strip_prefix('!') {
+            if let Some((former, latter)) = parse_rule(line)? {
+                digraph.entry(former).or_default().remove(&latter);
+            }
+        } else if let Some((former, latter)) = parse_rule(line)? {
             digraph.entry(former).or_default().insert(latter);
         }
     }
@@ -23,106 +93,774 @@ fn gen_digraph(input: &str) -> Result<HashMap<u32, HashSet<u32>>, Box<dyn Error>
     Ok(digraph)
 }
 
-fn toposort(digraph: &HashMap<u32, HashSet<u32>>) -> Vec<u32> {
-    let mut order = Vec::new();
-    let mut unvisited: HashSet<u32, RandomState> = HashSet::from_iter(digraph.keys().copied());
+/// A cycle in the rules, with the first page repeated at the end.
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Cycle(Vec<u32>);
 
-    fn toposort_visit(
-        digraph: &HashMap<u32, HashSet<u32>>,
-        order: &mut Vec<u32>,
-        unvisited: &mut HashSet<u32>,
-        node: u32,
-    ) {
-        if !unvisited.contains(&node) {
-            return;
+impl Display for Cycle {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0.iter().join(" → "))
+    }
+}
+
+#[derive(Error, Debug)]
+enum UpdateError {
+    #[error("update on line {line} ({}) can't be ordered, its rules have a cycle: {cycle}", pages.iter().join(","))]
+    Cycle {
+        line: usize,
+        pages: Vec<u32>,
+        cycle: Cycle,
+    },
+    #[error("update on line {line} has {pages} pages, can't count orderings of more than 64")]
+    TooLarge { line: usize, pages: usize },
+    #[error("update on line {line} has too many partial orderings to count (over {MAX_IDEALS} of the same length)")]
+    TooManyIdeals { line: usize },
+    #[error("update on line {line} has more orderings than fit in a u128")]
+    Overflow { line: usize },
+}
+
+/// For each of `pages`, the positions of the pages that the rules put directly
+/// after it, in order.
+fn rules_after(digraph: &HashMap<u32, HashSet<u32>>, pages: &[u32]) -> Vec<Vec<usize>> {
+    pages
+        .iter()
+        .map(|page| {
+            let latters = digraph.get(page);
+            (0..pages.len())
+                .filter(|&j| latters.is_some_and(|h| h.contains(&pages[j])))
+                .collect()
+        })
+        .collect()
+}
+
+/// Orders `pages` so that every rule between two of them is followed, or finds
+/// a cycle in those rules if that's impossible. Rules involving other pages
+/// don't matter. Pages that could go either way keep their original order:
+/// whenever there's a choice, the page that was earliest in `pages` goes next.
+fn toposort(digraph: &HashMap<u32, HashSet<u32>>, pages: &[u32]) -> Result<Vec<u32>, Cycle> {
+    // in order, so that even the cycle we find doesn't depend on hashing
+    let after = rules_after(digraph, pages);
+    match kahn(&after) {
+        Some(order) => Ok(order.into_iter().map(|i| pages[i]).collect()),
+        None => Err(find_cycle(&after, pages)),
+    }
+}
+
+/// Depth first search for a cycle, which has to exist since `kahn` got stuck.
+fn find_cycle(after: &[Vec<usize>], pages: &[u32]) -> Cycle {
+    #[derive(Clone, Copy, PartialEq, Eq)]
+    enum Mark {
+        Unvisited,
+        Visiting,
+        Done,
+    }
+
+    fn visit(i: usize, after: &[Vec<usize>], marks: &mut [Mark], path: &mut Vec<usize>) -> bool {
+        marks[i] = Mark::Visiting;
+        path.push(i);
+        for &j in &after[i] {
+            match marks[j] {
+                Mark::Visiting => {
+                    path.push(j);
+                    return true;
+                }
+                Mark::Unvisited => {
+                    if visit(j, after, marks, path) {
+                        return true;
+                    }
+                }
+                Mark::Done => {}
+            }
         }
+        path.pop();
+        marks[i] = Mark::Done;
+        false
+    }
+
+    let mut marks = vec![Mark::Unvisited; after.len()];
+    let mut path = Vec::new();
+    for i in 0..after.len() {
+        if marks[i] == Mark::Unvisited && visit(i, after, &mut marks, &mut path) {
+            // the path ends with the page it loops back to
+            let end = path[path.len() - 1];
+            let start = path.iter().position(|&k| k == end).unwrap();
+            return Cycle(path[start..].iter().map(|&k| pages[k]).collect());
+        }
+    }
+    unreachable!("kahn only fails on a cycle")
+}
+
+/// Why an update isn't in the right order.
+#[derive(Debug, PartialEq, Eq)]
+enum Violation {
+    /// Two pages the wrong way round, with the pages that say so starting
+    /// from the one that should have been first: just the rule
+    /// `chain[0]|chain[1]` unless checking strictly.
+    Broken { chain: Vec<u32> },
+    /// Strict only: neighbouring pages that no chain of rules puts in order.
+    Unordered { former: u32, latter: u32 },
+}
 
-        for outgoing_edge in digraph.get(&node).unwrap() {
-            toposort_visit(digraph, order, unvisited, *outgoing_edge);
+impl Display for Violation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Broken { chain } => {
+                let (first, last) = (chain[0], chain[chain.len() - 1]);
+                let rules = chain
+                    .iter()
+                    .tuple_windows()
+                    .map(|(former, latter)| format!("{former}|{latter}"))
+                    .join(", ");
+                if chain.len() == 2 {
+                    write!(f, "{first} must come before {last} (rule {rules})")
+                } else {
+                    write!(f, "{first} must come before {last} (rules {rules})")
+                }
+            }
+            Self::Unordered { former, latter } => {
+                write!(f, "no rule puts {former} before {latter}")
+            }
         }
+    }
+}
 
-        unvisited.remove(&node);
-        order.push(node);
+/// The shortest chain of rules from `from` to `to` through pages of the
+/// update, breadth first.
+fn chain(
+    from: u32,
+    to: u32,
+    update_pages: &[u32],
+    digraph: &HashMap<u32, HashSet<u32>>,
+) -> Option<Vec<u32>> {
+    let mut parents = HashMap::from([(from, from)]);
+    let mut queue = vec![from];
+    while !queue.is_empty() && !parents.contains_key(&to) {
+        let mut next = Vec::new();
+        for page in queue {
+            for &after in digraph.get(&page).into_iter().flatten() {
+                if update_pages.contains(&after) && !parents.contains_key(&after) {
+                    parents.insert(after, page);
+                    next.push(after);
+                }
+            }
+        }
+        queue = next;
     }
 
-    while let Some(node) = unvisited.iter().copied().next() {
-        toposort_visit(digraph, &mut order, &mut unvisited, node);
+    parents.contains_key(&to).then(|| {
+        let mut chain = vec![to];
+        while chain[chain.len() - 1] != from {
+            chain.push(parents[&chain[chain.len() - 1]]);
+        }
+        chain.reverse();
+        chain
+    })
+}
+
+/// Looks at every pair of pages in the update for one that's out of order,
+/// returning the first. With `Puzzle`, pairs without a rule between them can
+/// go either way. `Strict` also follows chains of rules through other pages
+/// of the update (which can blame an earlier pair), and then insists that
+/// each page is put before the next one by some chain, so the rules leave
+/// only one order.
+fn check(
+    update_pages: &[u32],
+    digraph: &HashMap<u32, HashSet<u32>>,
+    validity: Validity,
+) -> Option<Violation> {
+    for (i, &former) in update_pages.iter().enumerate() {
+        for &latter in &update_pages[i + 1..] {
+            let broken = match validity {
+                Validity::Puzzle => digraph
+                    .get(&latter)
+                    .is_some_and(|after| after.contains(&former))
+                    .then(|| vec![latter, former]),
+                Validity::Strict => chain(latter, former, update_pages, digraph),
+            };
+            if let Some(chain) = broken {
+                return Some(Violation::Broken { chain });
+            }
+        }
     }
 
-    order.reverse();
+    if validity == Validity::Strict {
+        for (former, latter) in update_pages.iter().copied().tuple_windows() {
+            if chain(former, latter, update_pages, digraph).is_none() {
+                return Some(Violation::Unordered { former, latter });
+            }
+        }
+    }
 
-    order
+    None
 }
 
-fn is_valid(update_pages: &[u32], digraph: &HashMap<u32, HashSet<u32>>) -> bool {
-    for (former, latter) in update_pages.iter().copied().tuple_windows() {
-        if !digraph
-            .get(&former)
-            .map(|h| h.contains(&latter))
-            .unwrap_or(false)
-        {
-            return false;
+fn is_valid(update_pages: &[u32], rules: &RuleSet, ctx: &Context) -> bool {
+    match rules.validate(update_pages) {
+        Some(violation) => {
+            ctx.debug(format_args!("{update_pages:?}: {violation}, invalid"));
+            false
+        }
+        None => {
+            ctx.trace(format_args!("{update_pages:?}: valid"));
+            true
         }
     }
+}
 
-    true
+/// An update's pages, and the line it was on for error messages.
+#[derive(Debug)]
+struct Update {
+    line: usize,
+    pages: Vec<u32>,
+}
+
+impl Update {
+    fn cycle(&self, cycle: Cycle) -> UpdateError {
+        UpdateError::Cycle {
+            line: self.line,
+            pages: self.pages.clone(),
+            cycle,
+        }
+    }
 }
 
-pub(crate) fn day_05(input: &str, part: Part) -> Result<String, Box<dyn Error>> {
-    let digraph = gen_digraph(input)?;
+fn parse_update(line: &str) -> Result<Vec<u32>, ParseIntError> {
+    line.split(',').map(|i| i.parse::<u32>()).collect()
+}
 
-    let mut sum = 0;
-    'line: for line in input.lines() {
+fn gen_updates(input: &str) -> Result<Vec<Update>, Box<dyn Error>> {
+    let mut updates = Vec::new();
+    for (i, line) in input.lines().enumerate() {
         if line.contains(',') {
-            let mut update_pages = line
-                .split(',')
-                .map(|i| i.parse::<u32>())
-                .collect::<Result<Vec<_>, _>>()?;
-
-            match part {
-                Part::PartOne => {
-                    if !is_valid(&update_pages, &digraph) {
-                        continue 'line;
+            let pages = parse_update(line)?;
+            updates.push(Update { line: i + 1, pages });
+        }
+    }
+
+    Ok(updates)
+}
+
+/// The rules, kept up to date as they change, for validating and fixing
+/// updates as they come. Remembers how it fixed each update until a rule
+/// between two of its pages changes, or it's remembering too many.
+#[derive(Debug)]
+struct RuleSet {
+    digraph: HashMap<u32, HashSet<u32>>,
+    validity: Validity,
+    /// Id of each remembered update, by its pages in their original order
+    /// since ties depend on it.
+    ids: HashMap<Vec<u32>, usize>,
+    orders: HashMap<usize, Remembered>,
+    /// Page -> ids of the remembered updates it's in, so a rule change only
+    /// has to look at updates with both of its pages.
+    index: HashMap<u32, HashSet<usize>>,
+    next_id: usize,
+}
+
+/// An update a `RuleSet` has fixed, and how.
+#[derive(Debug)]
+struct Remembered {
+    pages: Vec<u32>,
+    order: Result<Vec<u32>, Cycle>,
+}
+
+/// Most orders a `RuleSet` remembers before starting over. Crude, but a
+/// stream of queries that never repeats would otherwise grow it forever.
+const MAX_ORDERS: usize = 1 << 16;
+
+impl RuleSet {
+    fn new(digraph: HashMap<u32, HashSet<u32>>, validity: Validity) -> Self {
+        Self {
+            digraph,
+            validity,
+            ids: HashMap::new(),
+            orders: HashMap::new(),
+            index: HashMap::new(),
+            next_id: 0,
+        }
+    }
+
+    fn add_rule(&mut self, former: u32, latter: u32) {
+        if self.digraph.entry(former).or_default().insert(latter) {
+            self.forget(former, latter);
+        }
+    }
+
+    fn remove_rule(&mut self, former: u32, latter: u32) {
+        if self
+            .digraph
+            .get_mut(&former)
+            .is_some_and(|latters| latters.remove(&latter))
+        {
+            self.forget(former, latter);
+        }
+    }
+
+    /// Drops the orders that a rule between `a` and `b` could change - an
+    /// update's order only depends on the rules between its own pages.
+    fn forget(&mut self, a: u32, b: u32) {
+        let (Some(with_a), Some(with_b)) = (self.index.get(&a), self.index.get(&b)) else {
+            return;
+        };
+        let stale = with_a.intersection(with_b).copied().collect::<Vec<_>>();
+        for id in stale {
+            let Remembered { pages, .. } = self.orders.remove(&id).unwrap();
+            for page in &pages {
+                if let Some(ids) = self.index.get_mut(page) {
+                    ids.remove(&id);
+                    if ids.is_empty() {
+                        self.index.remove(page);
                     }
+                }
+            }
+            self.ids.remove(&pages);
+        }
+    }
+
+    fn validate(&self, update_pages: &[u32]) -> Option<Violation> {
+        check(update_pages, &self.digraph, self.validity)
+    }
 
-                    sum += update_pages[update_pages.len() / 2];
+    fn fix(&mut self, update: &Update) -> Result<Vec<u32>, UpdateError> {
+        let order = match self.ids.get(&update.pages) {
+            Some(id) => self.orders[id].order.clone(),
+            None => {
+                let order = toposort(&self.digraph, &update.pages);
+                if self.orders.len() >= MAX_ORDERS {
+                    self.ids.clear();
+                    self.orders.clear();
+                    self.index.clear();
+                }
+                let id = self.next_id;
+                self.next_id += 1;
+                for &page in &update.pages {
+                    self.index.entry(page).or_default().insert(id);
                 }
-                Part::PartTwo => {
-                    if is_valid(&update_pages, &digraph) {
-                        continue 'line;
+                self.ids.insert(update.pages.clone(), id);
+                self.orders.insert(
+                    id,
+                    Remembered {
+                        pages: update.pages.clone(),
+                        order: order.clone(),
+                    },
+                );
+                order
+            }
+        };
+        order.map_err(|cycle| update.cycle(cycle))
+    }
+}
+
+/// Most sets of pages that can start an update, of any one size, that
+/// `count_orderings` will keep track of.
+const MAX_IDEALS: usize = 1 << 18;
+
+/// How many orders the update's pages can go in without breaking any rule
+/// between them (zero if the rules have a cycle).
+///
+/// Goes through the sets of pages that can come first in a valid order (the
+/// down-sets), one size at a time, keeping count of the ways to put each set's
+/// pages in order. Any set of pages is an order's prefix as long as everything
+/// that must come before them is in there too, so the counts for the sets one
+/// bigger follow by adding each page that's allowed next.
+fn count_orderings(
+    update: &Update,
+    digraph: &HashMap<u32, HashSet<u32>>,
+) -> Result<u128, UpdateError> {
+    let pages = &update.pages;
+    if pages.len() > 64 {
+        return Err(UpdateError::TooLarge {
+            line: update.line,
+            pages: pages.len(),
+        });
+    }
+
+    // bit j of before[i] is set if pages[j] has to come before pages[i]
+    let before = pages
+        .iter()
+        .map(|page| {
+            pages
+                .iter()
+                .enumerate()
+                .filter(|(_, other)| digraph.get(other).is_some_and(|h| h.contains(page)))
+                .fold(0u64, |mask, (j, _)| mask | 1 << j)
+        })
+        .collect::<Vec<_>>();
+
+    let all = u64::MAX.checked_shr(64 - pages.len() as u32).unwrap_or(0);
+    let mut layer = HashMap::from([(0u64, 1u128)]);
+    for _ in 0..pages.len() {
+        let mut next = HashMap::new();
+        for (&placed, &ways) in &layer {
+            let mut unplaced = all & !placed;
+            while unplaced != 0 {
+                let i = unplaced.trailing_zeros();
+                unplaced &= unplaced - 1;
+                if placed & before[i as usize] == before[i as usize] {
+                    let count: &mut u128 = next.entry(placed | 1 << i).or_default();
+                    *count = count
+                        .checked_add(ways)
+                        .ok_or(UpdateError::Overflow { line: update.line })?;
+                }
+            }
+            if next.len() > MAX_IDEALS {
+                return Err(UpdateError::TooManyIdeals { line: update.line });
+            }
+        }
+        layer = next;
+    }
+
+    Ok(layer.into_values().sum())
+}
+
+/// Kahn's algorithm over positions `0..after.len()`, where `after[i]` lists
+/// the positions that have to come after `i`. Whenever there's a choice, the
+/// earliest position goes next. `None` if there's a cycle.
+fn kahn(after: &[Vec<usize>]) -> Option<Vec<usize>> {
+    let mut incoming = vec![0; after.len()];
+    for &j in after.iter().flatten() {
+        incoming[j] += 1;
+    }
+
+    let mut ready = (0..after.len())
+        .filter(|&i| incoming[i] == 0)
+        .map(Reverse)
+        .collect::<BinaryHeap<_>>();
+    let mut order = Vec::with_capacity(after.len());
+    while let Some(Reverse(i)) = ready.pop() {
+        order.push(i);
+        for &j in &after[i] {
+            incoming[j] -= 1;
+            if incoming[j] == 0 {
+                ready.push(Reverse(j));
+            }
+        }
+    }
+
+    (order.len() == after.len()).then_some(order)
+}
+
+/// The least disruptive fix for an update.
+#[derive(Debug, PartialEq, Eq)]
+struct Repair {
+    /// Pages that had to move, with their (0-based) positions in `order`, in
+    /// the order they were in the update.
+    moves: Vec<(u32, usize)>,
+    order: Vec<u32>,
+}
+
+/// Finds the fewest pages to move so the update follows its rules.
+///
+/// Say one page is "behind" another if it's later in the update but the rules
+/// (through any chain of the update's pages) say it should be earlier. That's a
+/// partial order, and the pages that can stay put are exactly the sets where
+/// no page is behind another - its antichains. By Dilworth's theorem, the
+/// largest antichain is as big as the fewest chains needed to cover every
+/// page, which by König's theorem is the pages minus a maximum matching of
+/// "behind" pairs; the vertex cover from that matching says which pages are in
+/// the antichain. The rest move, to wherever a toposort that keeps the staying
+/// pages in order puts them.
+fn repair(update: &Update, digraph: &HashMap<u32, HashSet<u32>>) -> Result<Repair, UpdateError> {
+    let pages = &update.pages;
+    let n = pages.len();
+    toposort(digraph, pages).map_err(|cycle| update.cycle(cycle))?;
+
+    // after[i] are the positions of pages the rules put directly after pages[i]
+    let after = rules_after(digraph, pages);
+
+    // reaches[i][j]: pages[j] has to come after pages[i]
+    let mut reaches = vec![vec![false; n]; n];
+    for (i, reach) in reaches.iter_mut().enumerate() {
+        let mut stack = vec![i];
+        while let Some(k) = stack.pop() {
+            for &j in &after[k] {
+                if !reach[j] {
+                    reach[j] = true;
+                    stack.push(j);
+                }
+            }
+        }
+    }
+    // behind[i]: later positions whose pages should come before pages[i]
+    let behind = (0..n)
+        .map(|i| (i + 1..n).filter(|&j| reaches[j][i]).collect::<Vec<_>>())
+        .collect::<Vec<_>>();
+
+    // Kuhn's algorithm, with left side i matched to right side j for j behind i
+    fn augment(
+        i: usize,
+        behind: &[Vec<usize>],
+        matched: &mut [Option<usize>],
+        seen: &mut [bool],
+    ) -> bool {
+        for &j in &behind[i] {
+            if !seen[j] {
+                seen[j] = true;
+                if matched[j].is_none_or(|k| augment(k, behind, matched, seen)) {
+                    matched[j] = Some(i);
+                    return true;
+                }
+            }
+        }
+        false
+    }
+    let mut matched = vec![None; n];
+    for i in 0..n {
+        augment(i, &behind, &mut matched, &mut vec![false; n]);
+    }
+
+    // alternating paths from unmatched left vertices, for König's cover
+    let mut left_matched = vec![false; n];
+    for i in matched.iter().flatten() {
+        left_matched[*i] = true;
+    }
+    let (mut left_reached, mut right_reached) = (vec![false; n], vec![false; n]);
+    let mut stack = (0..n).filter(|&i| !left_matched[i]).collect::<Vec<_>>();
+    for &i in &stack {
+        left_reached[i] = true;
+    }
+    while let Some(i) = stack.pop() {
+        for &j in &behind[i] {
+            if !right_reached[j] && matched[j] != Some(i) {
+                right_reached[j] = true;
+                if let Some(k) = matched[j] {
+                    if !left_reached[k] {
+                        left_reached[k] = true;
+                        stack.push(k);
                     }
+                }
+            }
+        }
+    }
+    // the cover is the unreached left vertices and the reached right ones;
+    // pages in neither half of it can stay
+    let stays = (0..n)
+        .map(|i| left_reached[i] && !right_reached[i])
+        .collect::<Vec<_>>();
+
+    // keep the staying pages in their order while sorting
+    let mut constraints = after;
+    let kept = (0..n).filter(|&i| stays[i]).collect::<Vec<_>>();
+    for (&i, &j) in kept.iter().tuple_windows() {
+        constraints[i].push(j);
+    }
+    let order = kahn(&constraints).expect("staying pages already agree with the rules");
 
-                    // need to toposort anew on each iteration since the entire graph
-                    // is not acyclic (but each relevant component is)
-                    let mut trim = digraph.clone();
-                    trim.retain(|k, _| update_pages.contains(k));
-                    let toposort = toposort(&trim);
+    let moves = (0..n)
+        .filter(|&i| !stays[i])
+        .map(|i| (pages[i], order.iter().position(|&k| k == i).unwrap()))
+        .collect();
+    Ok(Repair {
+        moves,
+        order: order.into_iter().map(|i| pages[i]).collect(),
+    })
+}
 
-                    let order = toposort
-                        .iter()
-                        .copied()
-                        .enumerate()
-                        .map(|(a, b)| (b, a))
-                        .collect::<HashMap<_, _>>();
+/// The rule graph in Graphviz's DOT language, or with an update just the rules
+/// between its pages: those it breaks are red, and each page is labelled with
+/// where it is and where it should be.
+fn dot(digraph: &HashMap<u32, HashSet<u32>>, update: Option<&Update>) -> String {
+    let pages = match update {
+        Some(update) => update.pages.iter().copied().sorted().dedup().collect(),
+        None => digraph
+            .iter()
+            .flat_map(|(&former, latters)| latters.iter().copied().chain([former]))
+            .sorted()
+            .dedup()
+            .collect::<Vec<_>>(),
+    };
+    let edges = digraph
+        .iter()
+        .flat_map(|(&former, latters)| latters.iter().map(move |&latter| (former, latter)))
+        .filter(|(former, latter)| pages.contains(former) && pages.contains(latter))
+        .sorted();
 
-                    update_pages.sort_unstable_by(|a, b| {
-                        order
-                            .get(a)
-                            .unwrap_or(&usize::MAX)
-                            .cmp(order.get(b).unwrap_or(&usize::MAX))
-                    });
-                    sum += update_pages[update_pages.len() / 2];
+    let mut lines = vec!["digraph rules {".to_string()];
+    let mut positions = HashMap::new();
+    let mut cycle_edges = HashSet::new();
+    match update {
+        Some(update) => {
+            // the entire graph is not acyclic, so just the update's part of
+            // it might not be either, and then there's no order to correct
+            // to but the cycle shows why
+            let corrected = match toposort(digraph, &update.pages) {
+                Ok(corrected) => Some(corrected),
+                Err(cycle) => {
+                    cycle_edges.extend(cycle.0.iter().copied().tuple_windows::<(_, _)>());
+                    lines.push(format!(
+                        "    label=\"update on line {}: {}\\ncycle: {cycle}\";",
+                        update.line,
+                        update.pages.iter().join(",")
+                    ));
+                    None
+                }
+            };
+            if let Some(corrected) = &corrected {
+                lines.push(format!(
+                    "    label=\"update on line {}: {}\\ncorrected: {}\";",
+                    update.line,
+                    update.pages.iter().join(","),
+                    corrected.iter().join(",")
+                ));
+            }
+            for page in &pages {
+                let from = update.pages.iter().position(|p| p == page).unwrap();
+                positions.insert(*page, from);
+                match &corrected {
+                    Some(corrected) => {
+                        let to = corrected.iter().position(|p| p == page).unwrap();
+                        lines.push(format!(
+                            "    {page} [label=\"{page}\\n#{} → #{}\"];",
+                            from + 1,
+                            to + 1
+                        ));
+                    }
+                    None => lines.push(format!("    {page} [label=\"{page}\\n#{}\"];", from + 1)),
                 }
             }
         }
+        None => lines.extend(pages.iter().map(|page| format!("    {page};"))),
     }
 
+    for (former, latter) in edges {
+        let broken = update.is_some() && positions[&latter] < positions[&former];
+        if cycle_edges.contains(&(former, latter)) {
+            lines.push(format!(
+                "    {former} -> {latter} [color=orange, penwidth=2];"
+            ));
+        } else if broken {
+            lines.push(format!("    {former} -> {latter} [color=red, penwidth=2];"));
+        } else {
+            lines.push(format!("    {former} -> {latter};"));
+        }
+    }
+    lines.push("}".into());
+
+    lines.join("\n")
+}
+
+/// Validates and fixes each update with just the rules before it, which
+/// can change along the way.
+fn queries(input: &str, validity: Validity, ctx: &Context) -> Result<String, Box<dyn Error>> {
+    let mut rules = RuleSet::new(HashMap::new(), validity);
+    let mut verdicts = Vec::new();
+    for (i, line) in input.lines().enumerate() {
+        if let Some(rule) = line.strip_prefix('!') {
+            if let Some((former, latter)) = parse_rule(rule)? {
+                rules.remove_rule(former, latter);
+            }
+        } else if let Some((former, latter)) = parse_rule(line)? {
+            rules.add_rule(former, latter);
+        } else if line.contains(',') {
+            let update = Update {
+                line: i + 1,
+                pages: parse_update(line)?,
+            };
+            let verdict = match rules.validate(&update.pages) {
+                None => "valid".into(),
+                // a cycle only stops this update being fixed, not the rest
+                Some(violation) => match rules.fix(&update) {
+                    Ok(order) => format!("{violation}, fixed to {}", order.iter().join(",")),
+                    Err(UpdateError::Cycle { cycle, .. }) => {
+                        format!("{violation}, can't be fixed, its rules have a cycle: {cycle}")
+                    }
+                    Err(e) => return Err(e.into()),
+                },
+            };
+            verdicts.push(format!("{line}: {verdict}"));
+        }
+    }
+    ctx.debug(format_args!("{} orders remembered", rules.orders.len()));
+
+    Ok(verdicts.join("\n"))
+}
+
+pub(crate) fn day_05(input: &str, part: Part, ctx: &Context) -> Result<String, Box<dyn Error>> {
+    let validity = ctx.params.get::<Validity>(&VALIDITY)?;
+    if part == Part::Extra("queries") {
+        return queries(input, validity, ctx);
+    }
+
+    let mut rules = RuleSet::new(gen_digraph(input)?, validity);
+    let updates = gen_updates(input)?;
+
+    if part == Part::Extra("dot") {
+        let update = match ctx.params.get::<usize>(&UPDATE)? {
+            0 => None,
+            n => Some(
+                updates
+                    .get(n - 1)
+                    .ok_or_else(|| format!("no update {n}, there are only {}", updates.len()))?,
+            ),
+        };
+        return Ok(dot(&rules.digraph, update));
+    }
+
+    let mut sum = 0;
+    let mut verdicts = Vec::new();
+    for update in &updates {
+        let update_pages = &update.pages;
+        match part {
+            Part::PartOne => {
+                if !is_valid(update_pages, &rules, ctx) {
+                    continue;
+                }
+
+                sum += update_pages[update_pages.len() / 2];
+            }
+            Part::PartTwo => {
+                if is_valid(update_pages, &rules, ctx) {
+                    continue;
+                }
+
+                let update_pages = rules.fix(update)?;
+                ctx.debug(format_args!("reordered to {update_pages:?}"));
+                sum += update_pages[update_pages.len() / 2];
+            }
+            Part::Extra("validate") => {
+                let verdict = match rules.validate(update_pages) {
+                    Some(violation) => violation.to_string(),
+                    None => "valid".into(),
+                };
+                verdicts.push(format!("{}: {verdict}", update_pages.iter().join(",")));
+            }
+            Part::Extra("repair") => {
+                // like orderings, a cycle is just this update's answer
+                let verdict = match repair(update, &rules.digraph) {
+                    Ok(Repair { moves, .. }) if moves.is_empty() => "nothing to move".into(),
+                    Ok(Repair { moves, order }) => format!(
+                        "move {} to get {}",
+                        moves
+                            .iter()
+                            .map(|(page, position)| format!("{page} to #{}", position + 1))
+                            .join(", "),
+                        order.iter().join(",")
+                    ),
+                    Err(UpdateError::Cycle { cycle, .. }) => {
+                        format!("can't be repaired, its rules have a cycle: {cycle}")
+                    }
+                    Err(e) => return Err(e.into()),
+                };
+                verdicts.push(format!("{}: {verdict}", update_pages.iter().join(",")));
+            }
+            Part::Extra("orderings") => {
+                let count = count_orderings(update, &rules.digraph)?;
+                verdicts.push(format!("{}: {count}", update_pages.iter().join(",")));
+            }
+            Part::Extra(_) => return Err(part.unsupported()),
+        }
+    }
+
+    if let Part::Extra("validate" | "orderings" | "repair") = part {
+        return Ok(verdicts.join("\n"));
+    }
     Ok(sum.to_string())
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::common::Params;
 
     const EXAMPLE_INPUT: &str = r"47|53
 97|13
@@ -155,19 +893,538 @@ mod tests {
 
     #[test]
     fn example_input() {
-        assert_eq!(&day_05(EXAMPLE_INPUT, Part::PartOne).unwrap(), "143");
-        assert_eq!(&day_05(EXAMPLE_INPUT, Part::PartTwo).unwrap(), "123");
+        assert_eq!(
+            &day_05(EXAMPLE_INPUT, Part::PartOne, &Context::default()).unwrap(),
+            "143"
+        );
+        assert_eq!(
+            &day_05(EXAMPLE_INPUT, Part::PartTwo, &Context::default()).unwrap(),
+            "123"
+        );
+    }
+
+    #[test]
+    fn example_input_logs() {
+        let ctx = Context::capturing(1);
+        day_05(EXAMPLE_INPUT, Part::PartTwo, &ctx).unwrap();
+        assert_eq!(
+            ctx.captured(),
+            vec![
+                "[75, 97, 47, 61, 53]: 97 must come before 75 (rule 97|75), invalid",
+                "reordered to [97, 75, 47, 61, 53]",
+                "[61, 13, 29]: 29 must come before 13 (rule 29|13), invalid",
+                "reordered to [61, 29, 13]",
+                "[97, 13, 75, 29, 47]: 75 must come before 13 (rule 75|13), invalid",
+                "reordered to [97, 75, 47, 29, 13]",
+            ]
+        );
+    }
+
+    #[test]
+    fn example_input_validate() {
+        for validity in ["puzzle", "strict"] {
+            let ctx = Context::with_params(Params::from_iter([("validity", validity)]));
+            assert_eq!(
+                day_05(EXAMPLE_INPUT, Part::Extra("validate"), &ctx)
+                    .unwrap()
+                    .lines()
+                    .collect::<Vec<_>>(),
+                vec![
+                    "75,47,61,53,29: valid",
+                    "97,61,53,29,13: valid",
+                    "75,29,13: valid",
+                    "75,97,47,61,53: 97 must come before 75 (rule 97|75)",
+                    "61,13,29: 29 must come before 13 (rule 29|13)",
+                    "97,13,75,29,47: 75 must come before 13 (rule 75|13)",
+                ]
+            );
+        }
+
+        // 47 and 61 could go either way, which is only fine for the puzzle
+        let input = "47|53\n53|29\n61|13\n\n47,53,29\n47,61,53\n61,13";
+        let verdicts = |validity| {
+            let ctx = Context::with_params(Params::from_iter([("validity", validity)]));
+            let verdicts = day_05(input, Part::Extra("validate"), &ctx).unwrap();
+            let part_one = day_05(input, Part::PartOne, &ctx).unwrap();
+            (verdicts, part_one)
+        };
+        assert_eq!(
+            verdicts("puzzle"),
+            (
+                "47,53,29: valid\n47,61,53: valid\n61,13: valid".to_string(),
+                "127".to_string()
+            )
+        );
+        assert_eq!(
+            verdicts("strict"),
+            (
+                "47,53,29: valid\n47,61,53: no rule puts 47 before 61\n61,13: valid".to_string(),
+                "66".to_string()
+            )
+        );
+    }
+
+    #[test]
+    fn check_pairs() {
+        // 47 and 29 are only ordered through 53
+        let digraph = gen_digraph("47|53\n53|29\n61|13").unwrap();
+        for validity in [Validity::Puzzle, Validity::Strict] {
+            assert_eq!(
+                check(&[53, 47], &digraph, validity),
+                Some(Violation::Broken {
+                    chain: vec![47, 53]
+                })
+            );
+        }
+
+        // no rule between neighbours is only a problem when strict
+        assert_eq!(
+            check(&[47, 61, 53, 13, 29], &digraph, Validity::Puzzle),
+            None
+        );
+        let violation = check(&[47, 61, 53, 13, 29], &digraph, Validity::Strict).unwrap();
+        assert_eq!(
+            violation,
+            Violation::Unordered {
+                former: 47,
+                latter: 61
+            }
+        );
+        assert_eq!(violation.to_string(), "no rule puts 47 before 61");
+        // but through other pages is fine
+        assert_eq!(check(&[47, 53, 29], &digraph, Validity::Strict), None);
+        assert_eq!(check(&[61, 13], &digraph, Validity::Strict), None);
+
+        assert_eq!(
+            check(&[29, 61, 47, 53], &digraph, Validity::Puzzle),
+            Some(Violation::Broken {
+                chain: vec![53, 29]
+            })
+        );
+        let violation = check(&[29, 61, 47, 53], &digraph, Validity::Strict).unwrap();
+        assert_eq!(
+            violation,
+            Violation::Broken {
+                chain: vec![47, 53, 29]
+            }
+        );
+        assert_eq!(
+            violation.to_string(),
+            "47 must come before 29 (rules 47|53, 53|29)"
+        );
+        // pages outside the update don't count
+        assert_eq!(
+            check(&[29, 61, 47], &digraph, Validity::Strict),
+            Some(Violation::Unordered {
+                former: 29,
+                latter: 61
+            })
+        );
+    }
+
+    #[test]
+    fn example_input_dot() {
+        let ctx = Context::with_params(Params::from_iter([("update", "5")]));
+        assert_eq!(
+            day_05(EXAMPLE_INPUT, Part::Extra("dot"), &ctx)
+                .unwrap()
+                .lines()
+                .collect::<Vec<_>>(),
+            vec![
+                "digraph rules {",
+                "    label=\"update on line 27: 61,13,29\\ncorrected: 61,29,13\";",
+                "    13 [label=\"13\\n#2 → #3\"];",
+                "    29 [label=\"29\\n#3 → #2\"];",
+                "    61 [label=\"61\\n#1 → #1\"];",
+                "    29 -> 13 [color=red, penwidth=2];",
+                "    61 -> 13;",
+                "    61 -> 29;",
+                "}",
+            ]
+        );
+
+        let dot = day_05(EXAMPLE_INPUT, Part::Extra("dot"), &Context::default()).unwrap();
+        assert_eq!(dot.lines().count(), 2 + 7 + 21);
+        assert!(dot.contains("\n    97 -> 75;\n"));
+
+        let input = "47|53\n53|29\n29|47\n29|13\n\n13,47,29,53";
+        let ctx = Context::with_params(Params::from_iter([("update", "1")]));
+        assert_eq!(
+            day_05(input, Part::Extra("dot"), &ctx)
+                .unwrap()
+                .lines()
+                .collect::<Vec<_>>(),
+            vec![
+                "digraph rules {",
+                "    label=\"update on line 6: 13,47,29,53\\ncycle: 47 → 53 → 29 → 47\";",
+                "    13 [label=\"13\\n#1\"];",
+                "    29 [label=\"29\\n#3\"];",
+                "    47 [label=\"47\\n#2\"];",
+                "    53 [label=\"53\\n#4\"];",
+                "    29 -> 13 [color=red, penwidth=2];",
+                "    29 -> 47 [color=orange, penwidth=2];",
+                "    47 -> 53 [color=orange, penwidth=2];",
+                "    53 -> 29 [color=orange, penwidth=2];",
+                "}",
+            ]
+        );
+
+        let ctx = Context::with_params(Params::from_iter([("update", "7")]));
+        assert_eq!(
+            day_05(EXAMPLE_INPUT, Part::Extra("dot"), &ctx)
+                .unwrap_err()
+                .to_string(),
+            "no update 7, there are only 6"
+        );
+    }
+
+    #[test]
+    fn orderings() {
+        let count = |rules: &str, pages: &[u32]| {
+            let update = Update {
+                line: 1,
+                pages: pages.to_vec(),
+            };
+            count_orderings(&update, &gen_digraph(rules).unwrap())
+        };
+
+        let pages = (1..=16).collect::<Vec<_>>();
+        assert_eq!(count("", &pages).unwrap(), (1..=16).product::<u128>());
+        assert_eq!(count("", &[]).unwrap(), 1);
+        // 1 has to go before 2, and both before 3 or 4 (in either order)
+        assert_eq!(count("1|2\n2|3\n2|4\n5|6", &[4, 3, 2, 1]).unwrap(), 2);
+        assert_eq!(count("1|2\n2|3\n3|1", &[1, 2, 3]).unwrap(), 0);
+        // two chains of three interleave in 6 choose 3 ways
+        assert_eq!(
+            count("1|2\n2|3\n4|5\n5|6", &[1, 2, 3, 4, 5, 6]).unwrap(),
+            20
+        );
+
+        let pages = (1..=65).collect::<Vec<_>>();
+        assert!(matches!(
+            count("", &pages),
+            Err(UpdateError::TooLarge { pages: 65, .. })
+        ));
+        let pages = (1..=40).collect::<Vec<_>>();
+        assert!(matches!(
+            count("", &pages),
+            Err(UpdateError::TooManyIdeals { .. })
+        ));
+        // five chains of (about) 13 pages: about 10^41 orderings, but only about
+        // half a million prefixes
+        let rules = (1..=64)
+            .filter(|page| page % 13 != 0)
+            .map(|page| format!("{page}|{}", page + 1))
+            .join("\n");
+        let pages = (1..=64).collect::<Vec<_>>();
+        assert!(matches!(
+            count(&rules, &pages),
+            Err(UpdateError::Overflow { line: 1 })
+        ));
+    }
+
+    #[test]
+    fn example_input_orderings() {
+        // the example's rules give a total order for each update
+        assert_eq!(
+            day_05(EXAMPLE_INPUT, Part::Extra("orderings"), &Context::default())
+                .unwrap()
+                .lines()
+                .map(|line| line.split_once(": ").unwrap().1)
+                .collect::<Vec<_>>(),
+            vec!["1"; 6]
+        );
+
+        let input = "47|53\n97|13\n\n47,53,97,13\n53,47";
+        assert_eq!(
+            day_05(input, Part::Extra("orderings"), &Context::default()).unwrap(),
+            "47,53,97,13: 6\n53,47: 1"
+        );
+    }
+
+    #[test]
+    fn example_input_repair() {
+        assert_eq!(
+            day_05(EXAMPLE_INPUT, Part::Extra("repair"), &Context::default())
+                .unwrap()
+                .lines()
+                .collect::<Vec<_>>(),
+            vec![
+                "75,47,61,53,29: nothing to move",
+                "97,61,53,29,13: nothing to move",
+                "75,29,13: nothing to move",
+                "75,97,47,61,53: move 75 to #2 to get 97,75,47,61,53",
+                "61,13,29: move 13 to #3 to get 61,29,13",
+                "97,13,75,29,47: move 13 to #5, 29 to #4 to get 97,75,47,29,13",
+            ]
+        );
+
+        let input = "47|53\n53|47\n\n53,47\n53,61";
+        assert_eq!(
+            day_05(input, Part::Extra("repair"), &Context::default()).unwrap(),
+            "53,47: can't be repaired, its rules have a cycle: 53 → 47 → 53\n53,61: nothing to move"
+        );
+    }
+
+    /// Fewest pages to move, the slow way: the biggest subsequence with no
+    /// page that the rules put after a later one, through any chain of the
+    /// update's pages.
+    fn brute_force_moves(pages: &[u32], digraph: &HashMap<u32, HashSet<u32>>) -> usize {
+        let reaches = |from: u32, to: u32| {
+            let mut stack = vec![from];
+            let mut seen = HashSet::new();
+            while let Some(page) = stack.pop() {
+                for &next in digraph.get(&page).into_iter().flatten() {
+                    if pages.contains(&next) && seen.insert(next) {
+                        stack.push(next);
+                    }
+                }
+            }
+            seen.contains(&to)
+        };
+
+        (0u32..1 << pages.len())
+            .filter(|kept| {
+                let kept = (0..pages.len())
+                    .filter(|i| kept & 1 << i != 0)
+                    .map(|i| pages[i])
+                    .collect::<Vec<_>>();
+                kept.iter()
+                    .tuple_combinations()
+                    .all(|(&a, &b)| !reaches(b, a))
+            })
+            .map(|kept| pages.len() - kept.count_ones() as usize)
+            .min()
+            .unwrap()
+    }
+
+    #[test]
+    fn repair_is_minimal() {
+        // a scrambled partial order on 1..=9, with pages 5 and 6 unordered
+        let rules = "1|2\n2|3\n3|4\n1|7\n7|8\n8|9\n2|8\n4|9\n5|9";
+        let digraph = gen_digraph(rules).unwrap();
+        let mut pages = (1..=9).collect::<Vec<u32>>();
+        let mut seed = 12345u32;
+        for _ in 0..200 {
+            // xorshift for a shuffle without pulling in rand
+            for i in (1..pages.len()).rev() {
+                seed ^= seed << 13;
+                seed ^= seed >> 17;
+                seed ^= seed << 5;
+                pages.swap(i, seed as usize % (i + 1));
+            }
+
+            let update = Update {
+                line: 1,
+                pages: pages.clone(),
+            };
+            let Repair { moves, order } = repair(&update, &digraph).unwrap();
+            assert_eq!(
+                moves.len(),
+                brute_force_moves(&pages, &digraph),
+                "{pages:?}"
+            );
+            assert!(
+                check(&order, &digraph, Validity::Puzzle).is_none(),
+                "{order:?}"
+            );
+            // everything that didn't move is still in the same order
+            let stayed = |pages: &[u32]| {
+                pages
+                    .iter()
+                    .filter(|page| !moves.iter().any(|(moved, _)| moved == *page))
+                    .copied()
+                    .collect::<Vec<_>>()
+            };
+            assert_eq!(stayed(&pages), stayed(&order));
+        }
+    }
+
+    #[test]
+    fn toposort_is_deterministic() {
+        let updates = gen_updates(EXAMPLE_INPUT).unwrap();
+        // a few pages with no rules between them, and one in the middle
+        let loose = [90, 80, 70, 47, 60, 50, 53];
+        let expected = updates
+            .iter()
+            .map(|update| toposort(&gen_digraph(EXAMPLE_INPUT).unwrap(), &update.pages).unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(expected[3], vec![97, 75, 47, 61, 53]);
+
+        // each digraph hashes differently
+        for _ in 0..200 {
+            let digraph = gen_digraph(EXAMPLE_INPUT).unwrap();
+            for (update, expected) in updates.iter().zip(&expected) {
+                assert_eq!(&toposort(&digraph, &update.pages).unwrap(), expected);
+            }
+            assert_eq!(
+                toposort(&digraph, &loose).unwrap(),
+                vec![90, 80, 70, 47, 60, 50, 53]
+            );
+            // 53 waits for 47, and 90 was there first
+            assert_eq!(toposort(&digraph, &[53, 90, 47]).unwrap(), vec![90, 47, 53]);
+        }
+    }
+
+    #[test]
+    fn rule_set() {
+        let update = |line, pages: &[u32]| Update {
+            line,
+            pages: pages.to_vec(),
+        };
+        let mut rules = RuleSet::new(gen_digraph("47|53\n97|75").unwrap(), Validity::Puzzle);
+        assert!(rules.validate(&[53, 47]).is_some());
+        assert_eq!(rules.fix(&update(1, &[53, 47])).unwrap(), vec![47, 53]);
+        assert_eq!(
+            rules.fix(&update(2, &[75, 97, 13])).unwrap(),
+            vec![97, 75, 13]
+        );
+        assert_eq!(rules.orders.len(), 2);
+
+        // only forgets orders with both pages in
+        rules.add_rule(13, 97);
+        assert_eq!(rules.orders.len(), 1);
+        assert_eq!(
+            rules.fix(&update(2, &[75, 97, 13])).unwrap(),
+            vec![13, 97, 75]
+        );
+        // nothing changes for a rule that's already there
+        rules.add_rule(13, 97);
+        assert_eq!(rules.orders.len(), 2);
+
+        rules.add_rule(53, 47);
+        assert_eq!(
+            rules.fix(&update(3, &[53, 47])).unwrap_err().to_string(),
+            "update on line 3 (53,47) can't be ordered, its rules have a cycle: 53 → 47 → 53"
+        );
+        // the cycle is remembered, but with the line of the update asked about
+        assert_eq!(
+            rules.fix(&update(4, &[53, 47])).unwrap_err().to_string(),
+            "update on line 4 (53,47) can't be ordered, its rules have a cycle: 53 → 47 → 53"
+        );
+        rules.remove_rule(47, 53);
+        assert!(rules.validate(&[53, 47]).is_none());
+        assert_eq!(rules.fix(&update(5, &[47, 53])).unwrap(), vec![53, 47]);
+        assert_eq!(rules.orders.len(), 2);
+        // and the index only has what's still remembered
+        let id = |pages: &[u32]| rules.ids[pages];
+        assert_eq!(rules.index[&53], HashSet::from([id(&[47, 53])]));
+        assert_eq!(rules.index[&97], HashSet::from([id(&[75, 97, 13])]));
+
+        // matches rebuilding everything from scratch
+        let from_scratch = RuleSet::new(
+            gen_digraph("47|53\n97|75\n13|97\n53|47\n!47|53").unwrap(),
+            Validity::Puzzle,
+        );
+        assert_eq!(rules.digraph, from_scratch.digraph);
+
+        // pages go from the index with the last order they're in
+        rules.remove_rule(97, 75);
+        assert_eq!(rules.orders.len(), 1);
+        assert_eq!(rules.ids.len(), 1);
+        let mut pages = rules.index.keys().copied().collect::<Vec<_>>();
+        pages.sort_unstable();
+        assert_eq!(pages, vec![47, 53]);
+    }
+
+    #[test]
+    fn example_input_queries() {
+        // the example, with the rules arriving between updates
+        let (rules, updates) = EXAMPLE_INPUT.split_once("\n\n").unwrap();
+        let input = format!("{updates}\n{rules}\n{updates}\n!97|75\n75|97\n{updates}");
+        let verdicts = day_05(&input, Part::Extra("queries"), &Context::default()).unwrap();
+        let verdicts = verdicts.lines().collect::<Vec<_>>();
+        assert_eq!(verdicts.len(), 18);
+        assert!(verdicts[..6]
+            .iter()
+            .all(|verdict| verdict.ends_with(": valid")));
+        assert_eq!(
+            verdicts[6..12],
+            [
+                "75,47,61,53,29: valid",
+                "97,61,53,29,13: valid",
+                "75,29,13: valid",
+                "75,97,47,61,53: 97 must come before 75 (rule 97|75), fixed to 97,75,47,61,53",
+                "61,13,29: 29 must come before 13 (rule 29|13), fixed to 61,29,13",
+                "97,13,75,29,47: 75 must come before 13 (rule 75|13), fixed to 97,75,47,29,13",
+            ]
+        );
+        assert_eq!(
+            verdicts[12..],
+            [
+                "75,47,61,53,29: valid",
+                "97,61,53,29,13: valid",
+                "75,29,13: valid",
+                "75,97,47,61,53: valid",
+                "61,13,29: 29 must come before 13 (rule 29|13), fixed to 61,29,13",
+                "97,13,75,29,47: 75 must come before 97 (rule 75|97), fixed to 75,97,47,29,13",
+            ]
+        );
+
+        // a cycle is just that update's verdict
+        let input = "47|53\n53|47\n53,47\n!53|47\n53,47";
+        assert_eq!(
+            day_05(input, Part::Extra("queries"), &Context::default())
+                .unwrap()
+                .lines()
+                .collect::<Vec<_>>(),
+            vec![
+                "53,47: 47 must come before 53 (rule 47|53), can't be fixed, its rules have a cycle: 53 → 47 → 53",
+                "53,47: 47 must come before 53 (rule 47|53), fixed to 47,53",
+            ]
+        );
+    }
+
+    #[test]
+    fn toposort_cycle() {
+        let digraph = gen_digraph("47|53\n53|29\n29|47\n29|13\n61|47").unwrap();
+        assert_eq!(toposort(&digraph, &[13, 61, 47]).unwrap(), vec![13, 61, 47]);
+        assert_eq!(toposort(&digraph, &[47, 13, 61]).unwrap(), vec![13, 61, 47]);
+        assert_eq!(
+            toposort(&digraph, &[47, 13, 29, 53]),
+            Err(Cycle(vec![47, 53, 29, 47]))
+        );
+        assert_eq!(
+            toposort(&digraph, &[29, 53, 47]).unwrap_err().to_string(),
+            "29 → 47 → 53 → 29"
+        );
+    }
+
+    #[test]
+    fn update_cycle() {
+        let input = "47|53\n53|29\n29|47\n61|47\n\n61,47,53\n47,29,53";
+        assert_eq!(
+            day_05(input, Part::PartTwo, &Context::default())
+                .unwrap_err()
+                .to_string(),
+            "update on line 7 (47,29,53) can't be ordered, its rules have a cycle: 47 → 53 → 29 → 47"
+        );
+        // part one doesn't need an order
+        assert_eq!(
+            &day_05(input, Part::PartOne, &Context::default()).unwrap(),
+            "47"
+        );
     }
 
     #[cfg(feature = "regression")]
     #[test]
     fn regression() {
         assert_eq!(
-            &day_05(include_str!("../inputs/05.txt"), Part::PartOne).unwrap(),
+            &day_05(
+                include_str!("../inputs/05.txt"),
+                Part::PartOne,
+                &Context::default()
+            )
+            .unwrap(),
             "6034"
         );
         assert_eq!(
-            &day_05(include_str!("../inputs/05.txt"), Part::PartTwo).unwrap(),
+            &day_05(
+                include_str!("../inputs/05.txt"),
+                Part::PartTwo,
+                &Context::default()
+            )
+            .unwrap(),
             "6305"
         );
     }
@@ -1,6 +1,6 @@
 use std::error::Error;
 
-use crate::common::Part;
+use crate::common::{Context, Part};
 
 #[derive(Clone, Copy, Debug, Hash, PartialEq, Eq)]
 enum Direction {
@@ -123,7 +123,7 @@ fn pathfind((mut x, mut y): (usize, usize), width: usize, grid: &mut [Cell]) ->
     PathResult::Escape { cover_area }
 }
 
-pub(crate) fn day_06(input: &str, part: Part) -> Result<String, Box<dyn Error>> {
+pub(crate) fn day_06(input: &str, part: Part, ctx: &Context) -> Result<String, Box<dyn Error>> {
     let mut position: (usize, usize) = (0, 0);
 
     let grid: Vec<Vec<Cell>> = input
@@ -184,6 +184,7 @@ pub(crate) fn day_06(input: &str, part: Part) -> Result<String, Box<dyn Error>>
                     match pathfind(position, width, &mut new_grid) {
                         PathResult::Escape { .. } => {}
                         PathResult::Cycle => {
+                            ctx.debug(format_args!("obstruction at ({ox}, {oy}) causes a cycle"));
                             cycle_count += 1;
                         }
                     }
@@ -191,12 +192,14 @@ pub(crate) fn day_06(input: &str, part: Part) -> Result<String, Box<dyn Error>>
             }
             Ok(cycle_count.to_string())
         }
+        Part::Extra(_) => Err(part.unsupported()),
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::runner::normalise;
 
     const EXAMPLE_INPUT: &str = r"....#.....
 .........#
@@ -211,19 +214,66 @@ mod tests {
 
     #[test]
     fn example_input() {
-        assert_eq!(&day_06(EXAMPLE_INPUT, Part::PartOne).unwrap(), "41");
-        assert_eq!(&day_06(EXAMPLE_INPUT, Part::PartTwo).unwrap(), "6");
+        assert_eq!(
+            &day_06(EXAMPLE_INPUT, Part::PartOne, &Context::default()).unwrap(),
+            "41"
+        );
+        assert_eq!(
+            &day_06(EXAMPLE_INPUT, Part::PartTwo, &Context::default()).unwrap(),
+            "6"
+        );
+    }
+
+    #[test]
+    fn example_input_crlf() {
+        let input = format!("\u{feff}{}\r\n", EXAMPLE_INPUT.replace('\n', "\r\n"));
+        let (input, _) = normalise(&input);
+        assert_eq!(
+            &day_06(&input, Part::PartOne, &Context::default()).unwrap(),
+            "41"
+        );
+        assert_eq!(
+            &day_06(&input, Part::PartTwo, &Context::default()).unwrap(),
+            "6"
+        );
+    }
+
+    #[test]
+    fn example_input_logs() {
+        let ctx = Context::capturing(1);
+        day_06(EXAMPLE_INPUT, Part::PartTwo, &ctx).unwrap();
+        assert_eq!(
+            ctx.captured(),
+            vec![
+                "obstruction at (3, 6) causes a cycle",
+                "obstruction at (6, 7) causes a cycle",
+                "obstruction at (7, 7) causes a cycle",
+                "obstruction at (1, 8) causes a cycle",
+                "obstruction at (3, 8) causes a cycle",
+                "obstruction at (7, 9) causes a cycle",
+            ]
+        );
     }
 
     #[cfg(feature = "regression")]
     #[test]
     fn regression() {
         assert_eq!(
-            &day_06(include_str!("../inputs/06.txt"), Part::PartOne).unwrap(),
+            &day_06(
+                include_str!("../inputs/06.txt"),
+                Part::PartOne,
+                &Context::default()
+            )
+            .unwrap(),
             "4778"
         );
         assert_eq!(
-            &day_06(include_str!("../inputs/06.txt"), Part::PartTwo).unwrap(),
+            &day_06(
+                include_str!("../inputs/06.txt"),
+                Part::PartTwo,
+                &Context::default()
+            )
+            .unwrap(),
             "1618"
         );
     }
@@ -1,13 +1,63 @@
-use std::error::Error;
+use std::{error::Error, str::FromStr};
 
-use crate::common::Part;
+use thiserror::Error;
+
+use crate::common::{Context, ParamSpec, Part};
+
+const OPERATORS: ParamSpec = ParamSpec {
+    name: "operators",
+    default: "+*",
+    help: "operators to try, any of + * | (concatenation); part two adds | unless set explicitly",
+    file: false,
+};
+
+pub(crate) const PARAMS: &[ParamSpec] = &[OPERATORS];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Operator {
+    Add,
+    Mul,
+    Concat,
+}
+
+impl Operator {
+    fn apply(&self, acc: u64, operand: u64) -> u64 {
+        match self {
+            Operator::Add => acc + operand,
+            Operator::Mul => acc * operand,
+            Operator::Concat => acc * 10u64.pow(operand.ilog10() + 1) + operand,
+        }
+    }
+}
+
+#[derive(Error, Debug)]
+#[error("Unknown operator {0:?}")]
+struct OperatorParseError(char);
+
+struct OperatorSet(Vec<Operator>);
+
+impl FromStr for OperatorSet {
+    type Err = OperatorParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.chars()
+            .map(|c| match c {
+                '+' => Ok(Operator::Add),
+                '*' => Ok(Operator::Mul),
+                '|' => Ok(Operator::Concat),
+                _ => Err(OperatorParseError(c)),
+            })
+            .collect::<Result<_, _>>()
+            .map(Self)
+    }
+}
 
 // I'm sure there's some kind of cool ring theory thing you can do on this one. No idea what it is.
 
 // Surprised this approach is fast enough but it actually works. I assume the early stop if acc > target
 // is doing a lot of lifting here.
 
-fn proc_row(acc: u64, ops: &[u64], target: u64, part: Part) -> bool {
+fn proc_row(acc: u64, ops: &[u64], target: u64, operators: &[Operator]) -> bool {
     if ops.is_empty() {
         return acc == target;
     }
@@ -16,21 +66,23 @@ fn proc_row(acc: u64, ops: &[u64], target: u64, part: Part) -> bool {
         return false;
     }
 
-    if part == Part::PartTwo {
-        let concat = acc * 10u64.pow(ops[0].ilog10() + 1) + ops[0];
-        if proc_row(concat, &ops[1..], target, part) {
-            return true;
-        }
-    }
-
-    if proc_row(acc * ops[0], &ops[1..], target, part) {
-        return true;
-    }
-
-    proc_row(acc + ops[0], &ops[1..], target, part)
+    operators
+        .iter()
+        .any(|operator| proc_row(operator.apply(acc, ops[0]), &ops[1..], target, operators))
 }
 
-pub(crate) fn day_07(input: &str, part: Part) -> Result<String, Box<dyn Error>> {
+pub(crate) fn day_07(input: &str, part: Part, ctx: &Context) -> Result<String, Box<dyn Error>> {
+    let OperatorSet(operators) = match ctx.params.get_explicit(&OPERATORS)? {
+        Some(operators) => operators,
+        None => {
+            let OperatorSet(mut operators) = ctx.params.get(&OPERATORS)?;
+            if part == Part::PartTwo {
+                operators.insert(0, Operator::Concat);
+            }
+            OperatorSet(operators)
+        }
+    };
+
     let mut sum = 0;
 
     for line in input.lines() {
@@ -41,7 +93,7 @@ pub(crate) fn day_07(input: &str, part: Part) -> Result<String, Box<dyn Error>>
             .map(|s| s.parse::<u64>())
             .collect::<Result<Vec<_>, _>>()?;
 
-        if proc_row(operands[0], &operands[1..], first, part) {
+        if proc_row(operands[0], &operands[1..], first, &operators) {
             sum += first;
         }
     }
@@ -51,6 +103,7 @@ pub(crate) fn day_07(input: &str, part: Part) -> Result<String, Box<dyn Error>>
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::common::Params;
 
     const EXAMPLE_INPUT: &str = r"190: 10 19
 3267: 81 40 27
@@ -64,19 +117,43 @@ mod tests {
 
     #[test]
     fn example_input() {
-        assert_eq!(&day_07(EXAMPLE_INPUT, Part::PartOne).unwrap(), "3749");
-        assert_eq!(&day_07(EXAMPLE_INPUT, Part::PartTwo).unwrap(), "11387");
+        assert_eq!(
+            &day_07(EXAMPLE_INPUT, Part::PartOne, &Context::default()).unwrap(),
+            "3749"
+        );
+        assert_eq!(
+            &day_07(EXAMPLE_INPUT, Part::PartTwo, &Context::default()).unwrap(),
+            "11387"
+        );
+    }
+
+    #[test]
+    fn example_input_params() {
+        let ctx = Context::with_params(Params::from_iter([("operators", "*")]));
+        assert_eq!(&day_07(EXAMPLE_INPUT, Part::PartOne, &ctx).unwrap(), "190");
+        let ctx = Context::with_params(Params::from_iter([("operators", "+|")]));
+        assert_eq!(&day_07(EXAMPLE_INPUT, Part::PartTwo, &ctx).unwrap(), "348");
     }
 
     #[cfg(feature = "regression")]
     #[test]
     fn regression() {
         assert_eq!(
-            &day_07(include_str!("../inputs/07.txt"), Part::PartOne).unwrap(),
+            &day_07(
+                include_str!("../inputs/07.txt"),
+                Part::PartOne,
+                &Context::default()
+            )
+            .unwrap(),
             "28730327770375"
         );
         assert_eq!(
-            &day_07(include_str!("../inputs/07.txt"), Part::PartTwo).unwrap(),
+            &day_07(
+                include_str!("../inputs/07.txt"),
+                Part::PartTwo,
+                &Context::default()
+            )
+            .unwrap(),
             "424977609625985"
         );
     }
@@ -1,7 +1,8 @@
-use std::path::PathBuf;
+use std::{error::Error, fs::File, path::PathBuf, time::Duration};
 
-use clap::Parser;
-use common::{DaySolver, Part};
+use cache::{Cache, Key};
+use clap::{ArgAction, Parser, Subcommand};
+use common::{Context, Day, ParamSpec, Params, Part};
 use day00::day_00;
 use day01::day_01;
 use day02::day_02;
@@ -11,6 +12,7 @@ use day05::day_05;
 use day06::day_06;
 use day07::day_07;
 
+mod cache;
 mod common;
 mod day00;
 mod day01;
@@ -20,27 +22,243 @@ mod day04;
 mod day05;
 mod day06;
 mod day07;
+mod runner;
 
 #[derive(Parser)]
+#[command(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
 struct Args {
-    day: usize,
-    part: Part,
-    source: PathBuf,
+    #[command(subcommand)]
+    command: Option<Command>,
+    #[command(flatten)]
+    run: RunArgs,
+}
+
+#[derive(clap::Args)]
+struct RunArgs {
+    #[arg(required = true)]
+    day: Option<usize>,
+    #[arg(required = true)]
+    part: Option<String>,
+    #[arg(required = true)]
+    source: Option<PathBuf>,
+    /// Override a solver parameter (see the `params` command)
+    #[arg(long = "param", value_name = "KEY=VALUE", value_parser = Params::parse_assignment)]
+    params: Vec<(String, String)>,
+    /// Print debug output from the solver to stderr (-vv for more); skips the cache
+    #[arg(short, long, action = ArgAction::Count)]
+    verbose: u8,
+    /// Pass the input through as-is, without fixing line endings etc.
+    #[arg(long)]
+    raw: bool,
+    /// Always run the solver, even if the answer is cached
+    #[arg(long)]
+    no_cache: bool,
+    /// Read the input incrementally instead of all at once (no normalisation or
+    /// caching, and only for days that support it)
+    #[arg(long)]
+    stream: bool,
+    /// Give up on the solver after this many seconds
+    #[arg(long, value_name = "SECONDS")]
+    timeout: Option<f64>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// List the parameters and extra parts a day's solver accepts
+    Params { day: usize },
+    /// Manage the answer cache
+    Cache {
+        #[command(subcommand)]
+        command: CacheCommand,
+    },
+}
+
+#[derive(Subcommand)]
+enum CacheCommand {
+    /// Delete all cached answers
+    Clear,
 }
 
 fn main() {
-    let days: Vec<Box<DaySolver>> = vec![
-        Box::new(day_00),
-        Box::new(day_01),
-        Box::new(day_02),
-        Box::new(day_03),
-        Box::new(day_04),
-        Box::new(day_05),
-        Box::new(day_06),
-        Box::new(day_07),
+    if let Err(e) = run() {
+        eprintln!("error: {e}");
+        std::process::exit(1);
+    }
+}
+
+fn run() -> Result<(), Box<dyn Error>> {
+    let days = [
+        Day {
+            solver: day_00,
+            stream_solver: None,
+            params: &[],
+            extra_parts: &[],
+        },
+        Day {
+            solver: day_01,
+            stream_solver: None,
+            params: &[],
+            extra_parts: &[],
+        },
+        Day {
+            solver: day_02,
+            stream_solver: None,
+            params: day02::PARAMS,
+            extra_parts: day02::EXTRA_PARTS,
+        },
+        Day {
+            solver: day_03,
+            stream_solver: Some(day03::day_03_stream),
+            params: day03::PARAMS,
+            extra_parts: day03::EXTRA_PARTS,
+        },
+        Day {
+            solver: day_04,
+            stream_solver: None,
+            params: day04::PARAMS,
+            extra_parts: day04::EXTRA_PARTS,
+        },
+        Day {
+            solver: day_05,
+            stream_solver: None,
+            params: day05::PARAMS,
+            extra_parts: day05::EXTRA_PARTS,
+        },
+        Day {
+            solver: day_06,
+            stream_solver: None,
+            params: &[],
+            extra_parts: &[],
+        },
+        Day {
+            solver: day_07,
+            stream_solver: None,
+            params: day07::PARAMS,
+            extra_parts: &[],
+        },
     ];
 
     let args = Args::parse();
-    let input = std::fs::read_to_string(args.source).unwrap();
-    println!("{}", days[args.day](&input, args.part).unwrap());
+    match args.command {
+        Some(Command::Params { day: day_number }) => {
+            let day = days
+                .get(day_number)
+                .ok_or_else(|| format!("no day {day_number}"))?;
+            if day.params.is_empty() {
+                println!("day {day_number} has no parameters");
+            }
+            for spec in day.params {
+                println!(
+                    "{} (default: {:?})\n    {}",
+                    spec.name, spec.default, spec.help
+                );
+            }
+            for spec in day.extra_parts {
+                println!("part {}\n    {}", spec.name, spec.help);
+            }
+        }
+        Some(Command::Cache {
+            command: CacheCommand::Clear,
+        }) => {
+            let cache = Cache::open()
+                .ok_or("no cache directory (neither XDG_CACHE_HOME nor HOME is set)")?;
+            cache.clear()?;
+            println!("cleared {}", cache.dir().display());
+        }
+        None => {
+            let run = args.run;
+            let day_number = run.day.unwrap();
+            let day = days
+                .get(day_number)
+                .ok_or_else(|| format!("no day {day_number}"))?;
+            let part = Part::parse(&run.part.unwrap(), day.extra_parts)?;
+            let params = run.params.into_iter().collect::<Params>();
+            params.check(day.params)?;
+
+            let source = run.source.unwrap();
+            let timeout = run.timeout.map(Duration::try_from_secs_f64).transpose()?;
+            runner::quiet_panics();
+
+            if run.stream {
+                let solver = day
+                    .stream_solver
+                    .ok_or_else(|| format!("day {day_number} can't stream its input"))?;
+                let mut file = File::open(source)?;
+                let answer = runner::run(
+                    move |ctx| solver(&mut file, part, ctx),
+                    Context::new(params, run.verbose),
+                    timeout,
+                )?;
+                println!("{answer}");
+                return Ok(());
+            }
+
+            let mut input = std::fs::read_to_string(source)?;
+            if !run.raw {
+                let (normalised, changes) = runner::normalise(&input);
+                for change in &changes {
+                    eprintln!("warning: {change} (use --raw to skip)");
+                }
+                input = normalised.into_owned();
+            }
+
+            // a cached answer wouldn't show the solver's logging
+            let cache = if run.no_cache || run.verbose > 0 {
+                None
+            } else {
+                open_cache(day_number, part, &input, &params, day.params)
+            };
+            if let Some((cache, key)) = &cache {
+                match cache.get(key) {
+                    Ok(Some(answer)) => {
+                        println!("{answer}");
+                        eprintln!("note: answer loaded from cache (use --no-cache to recompute)");
+                        return Ok(());
+                    }
+                    Ok(None) => {}
+                    Err(e) => eprintln!("warning: couldn't read cache: {e}"),
+                }
+            }
+
+            let solver = day.solver;
+            let answer = runner::run(
+                move |ctx| solver(&input, part, ctx),
+                Context::new(params, run.verbose),
+                timeout,
+            )?;
+
+            if let Some((cache, key)) = cache {
+                if let Err(e) = cache.put(&key, &answer) {
+                    eprintln!("warning: couldn't cache answer: {e}");
+                }
+            }
+            println!("{answer}");
+        }
+    }
+
+    Ok(())
+}
+
+/// The cache and where this run's answer goes in it, or `None` (after a
+/// warning if something went wrong) when there's nowhere to cache it.
+fn open_cache(
+    day_number: usize,
+    part: Part,
+    input: &str,
+    params: &Params,
+    specs: &[ParamSpec],
+) -> Option<(Cache, Key)> {
+    let cache = Cache::open()?;
+    let key = cache::build_id()
+        .map_err(|e| format!("couldn't identify this build: {e}"))
+        .and_then(|build_id| {
+            Key::new(day_number, part, input, params, specs, build_id).map_err(|e| e.to_string())
+        });
+    match key {
+        Ok(key) => Some((cache, key)),
+        Err(e) => {
+            eprintln!("warning: not caching, {e}");
+            None
+        }
+    }
 }
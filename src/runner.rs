@@ -0,0 +1,251 @@
+use std::{
+    any::Any,
+    borrow::Cow,
+    cell::{Cell, RefCell},
+    error::Error,
+    fmt::Display,
+    panic::{self, AssertUnwindSafe},
+    sync::mpsc,
+    thread,
+    time::Duration,
+};
+
+use thiserror::Error;
+
+use crate::common::Context;
+
+#[derive(Error, Debug)]
+pub(crate) enum RunError {
+    #[error("solver failed: {0}")]
+    Failed(String),
+    #[error("solver panicked{}: {message}", location.as_ref().map(|l| format!(" at {l}")).unwrap_or_default())]
+    Panicked {
+        message: String,
+        location: Option<String>,
+    },
+    #[error("solver did not finish within {0:?}")]
+    TimedOut(Duration),
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) enum Normalisation {
+    StrippedBom,
+    ConvertedCrlf { lines: usize },
+    TrimmedBlankLines { lines: usize },
+}
+
+impl Display for Normalisation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Normalisation::StrippedBom => write!(f, "stripped byte order mark"),
+            Normalisation::ConvertedCrlf { lines } => {
+                write!(f, "converted {lines} CRLF line ending(s) to LF")
+            }
+            Normalisation::TrimmedBlankLines { lines } => {
+                write!(f, "trimmed {lines} trailing blank line(s)")
+            }
+        }
+    }
+}
+
+/// Cleans up the usual damage from inputs that went through another OS or a
+/// browser: strips a BOM, converts CRLF to LF and drops whitespace-only lines
+/// at the end (keeping the last real line's newline). Returns what it had to
+/// change, if anything.
+pub(crate) fn normalise(input: &str) -> (Cow<'_, str>, Vec<Normalisation>) {
+    let mut changes = Vec::new();
+    let mut text = Cow::Borrowed(input);
+
+    if let Some(rest) = text.strip_prefix('\u{feff}') {
+        text = Cow::Owned(rest.to_string());
+        changes.push(Normalisation::StrippedBom);
+    }
+
+    let crlf = text.matches("\r\n").count();
+    if crlf > 0 {
+        text = Cow::Owned(text.replace("\r\n", "\n"));
+        changes.push(Normalisation::ConvertedCrlf { lines: crlf });
+    }
+
+    // whole whitespace-only lines only, trailing spaces on the last real line
+    // might mean something
+    let (blank, blank_len) = text
+        .split_inclusive('\n')
+        .rev()
+        .take_while(|line| line.trim().is_empty())
+        .fold((0, 0), |(lines, len), line| (lines + 1, len + line.len()));
+    if blank > 0 {
+        text = Cow::Owned(text[..text.len() - blank_len].to_string());
+        changes.push(Normalisation::TrimmedBlankLines { lines: blank });
+    }
+
+    (text, changes)
+}
+
+thread_local! {
+    static PANIC_LOCATION: RefCell<Option<String>> = const { RefCell::new(None) };
+    static ISOLATED: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Wraps the panic hook so that, inside `run_isolated`, it just remembers
+/// where the panic happened instead of printing the message and maybe a
+/// backtrace, since `run` reports it as a `RunError` anyway. Panics anywhere
+/// else still go to the previous hook.
+pub(crate) fn quiet_panics() {
+    let previous = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        if ISOLATED.get() {
+            let location = info.location().map(|l| l.to_string());
+            PANIC_LOCATION.with(|cell| *cell.borrow_mut() = location);
+        } else {
+            previous(info);
+        }
+    }));
+}
+
+fn panic_message(payload: Box<dyn Any + Send>) -> String {
+    if let Some(s) = payload.downcast_ref::<&str>() {
+        s.to_string()
+    } else if let Some(s) = payload.downcast_ref::<String>() {
+        s.clone()
+    } else {
+        "(non-string panic payload)".into()
+    }
+}
+
+fn run_isolated<F>(job: F, ctx: &Context) -> Result<String, RunError>
+where
+    F: FnOnce(&Context) -> Result<String, Box<dyn Error>>,
+{
+    // nothing from the solver's state survives a panic, so unwind safety doesn't matter here
+    let was_isolated = ISOLATED.replace(true);
+    let result = panic::catch_unwind(AssertUnwindSafe(|| job(ctx)));
+    ISOLATED.set(was_isolated);
+    match result {
+        Ok(Ok(answer)) => Ok(answer),
+        Ok(Err(e)) => Err(RunError::Failed(e.to_string())),
+        Err(payload) => Err(RunError::Panicked {
+            message: panic_message(payload),
+            location: PANIC_LOCATION.with(|cell| cell.borrow_mut().take()),
+        }),
+    }
+}
+
+/// Runs a solver (wrapped up with its input as `job`), turning errors and panics
+/// into a `RunError`. With a timeout, the solver runs on a worker thread that is
+/// abandoned (not killed) if it doesn't report back in time - the caller is
+/// expected to exit shortly after.
+pub(crate) fn run<F>(job: F, ctx: Context, timeout: Option<Duration>) -> Result<String, RunError>
+where
+    F: FnOnce(&Context) -> Result<String, Box<dyn Error>> + Send + 'static,
+{
+    let Some(timeout) = timeout else {
+        return run_isolated(job, &ctx);
+    };
+
+    let (tx, rx) = mpsc::channel();
+    thread::Builder::new()
+        .name("solver".into())
+        .spawn(move || {
+            // the receiver is gone if we already timed out, nobody to tell
+            let _ = tx.send(run_isolated(job, &ctx));
+        })
+        .map_err(|e| RunError::Failed(e.to_string()))?;
+
+    match rx.recv_timeout(timeout) {
+        Ok(result) => result,
+        Err(_) => Err(RunError::TimedOut(timeout)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::Part;
+
+    fn panics(_input: &str, _part: Part, _ctx: &Context) -> Result<String, Box<dyn Error>> {
+        panic!("oh no")
+    }
+
+    fn spins(_input: &str, _part: Part, _ctx: &Context) -> Result<String, Box<dyn Error>> {
+        loop {
+            thread::sleep(Duration::from_millis(10));
+        }
+    }
+
+    fn answers(input: &str, _part: Part, _ctx: &Context) -> Result<String, Box<dyn Error>> {
+        Ok(input.len().to_string())
+    }
+
+    #[test]
+    fn normalise_clean_input() {
+        let (text, changes) = normalise("a b\nc d\n");
+        assert!(matches!(text, Cow::Borrowed("a b\nc d\n")));
+        assert!(changes.is_empty());
+
+        let (text, changes) = normalise("no newline");
+        assert_eq!(text, "no newline");
+        assert!(changes.is_empty());
+    }
+
+    #[test]
+    fn normalise_messy_input() {
+        let (text, changes) = normalise("\u{feff}a b\r\nc d\r\n\r\n  \n");
+        assert_eq!(text, "a b\nc d\n");
+        assert_eq!(
+            changes,
+            vec![
+                Normalisation::StrippedBom,
+                Normalisation::ConvertedCrlf { lines: 3 },
+                Normalisation::TrimmedBlankLines { lines: 2 },
+            ]
+        );
+
+        // spaces at the end of the last line aren't a blank line
+        let (text, changes) = normalise("abc  \n\n \t\n");
+        assert_eq!(text, "abc  \n");
+        assert_eq!(changes, vec![Normalisation::TrimmedBlankLines { lines: 2 }]);
+    }
+
+    #[test]
+    fn panic_is_caught() {
+        for timeout in [None, Some(Duration::from_secs(5))] {
+            match run(
+                |ctx| panics("", Part::PartOne, ctx),
+                Context::default(),
+                timeout,
+            ) {
+                Err(RunError::Panicked { message, .. }) => assert_eq!(message, "oh no"),
+                other => panic!("unexpected {other:?}"),
+            }
+        }
+        // so panics after the solver's still go to the usual hook
+        assert!(!ISOLATED.get());
+    }
+
+    #[test]
+    fn timeout() {
+        let timeout = Duration::from_millis(50);
+        assert!(matches!(
+            run(
+                |ctx| spins("", Part::PartOne, ctx),
+                Context::default(),
+                Some(timeout)
+            ),
+            Err(RunError::TimedOut(t)) if t == timeout
+        ));
+    }
+
+    #[test]
+    fn answer_within_timeout() {
+        assert_eq!(
+            run(
+                |ctx| answers("abc", Part::PartOne, ctx),
+                Context::default(),
+                Some(Duration::from_secs(5))
+            )
+            .unwrap(),
+            "3"
+        );
+    }
+}